@@ -0,0 +1,920 @@
+#[cfg(feature = "json")]
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+// Reader and plaintext evaluator for the Bristol files written by
+// bristol_circuit, e.g. job_matching.txt. It is shared by the circuits
+// crate, which writes them, and laconic-ot, which garbles them, without
+// pulling in the pairing libraries of the latter. The format is
+//
+//   <number of gates> <number of wires>
+//   <number of inputs> <width of input 0> <width of input 1> ...
+//   <number of outputs> <width of output 0> ...
+//
+//   <fan-in> <fan-out> <input wires> <output wire> <gate>
+//   ...
+//
+// Inputs occupy the first wires in order, the outputs the last ones.
+// Every gate may only read wires that are inputs or were written by an
+// earlier gate, which the parser checks, so evaluation cannot fail
+// once the input widths match.
+//
+// The gates are XOR, AND, INV, EQ (a constant, given instead of the
+// input wire) and EQW (a copy) of Bristol Fashion, as well as the OR
+// and NOT written by bristol_circuit. Bristol Fashion itself has no
+// names, so they are kept in a JSON sidecar, see CircuitInfo
+
+/// A boolean gate, reading the wires a and b and writing out
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Gate {
+    Xor { a: usize, b: usize, out: usize },
+    And { a: usize, b: usize, out: usize },
+    Or { a: usize, b: usize, out: usize },
+    Not { a: usize, out: usize },
+    Const { value: bool, out: usize },
+    Copy { a: usize, out: usize },
+}
+
+impl Gate {
+    /// The wire written by the gate
+    pub fn output(&self) -> usize {
+        match *self {
+            Gate::Xor { out, .. }
+            | Gate::And { out, .. }
+            | Gate::Or { out, .. }
+            | Gate::Not { out, .. }
+            | Gate::Const { out, .. }
+            | Gate::Copy { out, .. } => out,
+        }
+    }
+
+    /// The wires read by the gate
+    pub fn inputs(&self) -> Vec<usize> {
+        match *self {
+            Gate::Xor { a, b, .. } | Gate::And { a, b, .. } | Gate::Or { a, b, .. } => vec![a, b],
+            Gate::Not { a, .. } | Gate::Copy { a, .. } => vec![a],
+            Gate::Const { .. } => vec![],
+        }
+    }
+
+    fn eval(&self, wires: &[bool]) -> bool {
+        match *self {
+            Gate::Xor { a, b, .. } => wires[a] ^ wires[b],
+            Gate::And { a, b, .. } => wires[a] & wires[b],
+            Gate::Or { a, b, .. } => wires[a] | wires[b],
+            Gate::Not { a, .. } => !wires[a],
+            Gate::Const { value, .. } => value,
+            Gate::Copy { a, .. } => wires[a],
+        }
+    }
+}
+
+impl fmt::Display for Gate {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match *self {
+            Gate::Xor { a, b, out } => write!(f, "2 1 {} {} {} XOR", a, b, out),
+            Gate::And { a, b, out } => write!(f, "2 1 {} {} {} AND", a, b, out),
+            Gate::Or { a, b, out } => write!(f, "2 1 {} {} {} OR", a, b, out),
+            Gate::Not { a, out } => write!(f, "1 1 {} {} INV", a, out),
+            Gate::Const { value, out } => write!(f, "1 1 {} {} EQ", u8::from(value), out),
+            Gate::Copy { a, out } => write!(f, "1 1 {} {} EQW", a, out),
+        }
+    }
+}
+
+/// A circuit in Bristol format
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct Circuit {
+    pub wire_count: usize,
+
+    /// the number of bits of each party input and output
+    pub input_widths: Vec<usize>,
+    pub output_widths: Vec<usize>,
+
+    pub gates: Vec<Gate>,
+}
+
+/// Errors of parsing or evaluating a Bristol circuit
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum BristolError {
+    /// the file ended before the header or all gates were read
+    UnexpectedEnd,
+
+    /// the token on the line is not a number
+    InvalidNumber { line: usize, token: String },
+
+    /// the gate type on the line is not XOR, AND, OR, INV or NOT
+    UnknownGate { line: usize, op: String },
+
+    /// the fan-in or fan-out on the line does not fit the gate type,
+    /// or the line does not have as many wires as announced
+    InvalidArity { line: usize },
+
+    /// the gate on the line reads a wire that is
+    /// neither an input nor written by an earlier gate
+    UnassignedWire { line: usize, wire: usize },
+
+    /// the gate on the line writes a wire that is
+    /// out of range, an input or already written
+    InvalidOutput { line: usize, wire: usize },
+
+    /// the header does not match the gates, e.g. the inputs
+    /// and outputs overlap, or its counts are too large for the text
+    InvalidHeader,
+
+    /// the wrong number of inputs was passed to evaluate,
+    /// or the ith input has the wrong number of bits
+    InputMismatch { index: usize },
+
+    /// the sidecar is not valid JSON
+    #[cfg(feature = "json")]
+    Json(String),
+
+    /// the names in the sidecar do not match the inputs or outputs
+    /// of the circuit or are not unique, or an input has a party
+    /// other than 0 and 1 or the parties are out of order
+    InvalidInfo,
+
+    /// no input or output has the name
+    UnknownName { name: String },
+
+    /// no value was given for the input
+    MissingInput { name: String },
+
+    /// the value of the input has the wrong number of bits
+    WidthMismatch {
+        name: String,
+        expected: usize,
+        found: usize,
+    },
+}
+
+impl fmt::Display for BristolError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BristolError::UnexpectedEnd => write!(f, "unexpected end of the circuit"),
+            BristolError::InvalidNumber { line, token } => {
+                write!(f, "line {}: {:?} is not a number", line, token)
+            }
+            BristolError::UnknownGate { line, op } => {
+                write!(f, "line {}: unknown gate {:?}", line, op)
+            }
+            BristolError::InvalidArity { line } => {
+                write!(f, "line {}: wrong number of wires for the gate", line)
+            }
+            BristolError::UnassignedWire { line, wire } => {
+                write!(
+                    f,
+                    "line {}: wire {} is read before it is assigned",
+                    line, wire
+                )
+            }
+            BristolError::InvalidOutput { line, wire } => {
+                write!(f, "line {}: wire {} cannot be written", line, wire)
+            }
+            BristolError::InvalidHeader => write!(f, "the header does not match the gates"),
+            BristolError::InputMismatch { index } => {
+                write!(f, "input {} does not match the circuit", index)
+            }
+            #[cfg(feature = "json")]
+            BristolError::Json(e) => write!(f, "invalid circuit info: {}", e),
+            BristolError::InvalidInfo => {
+                write!(f, "the circuit info does not match the circuit")
+            }
+            BristolError::UnknownName { name } => write!(f, "no input or output {:?}", name),
+            BristolError::MissingInput { name } => write!(f, "no value for input {:?}", name),
+            BristolError::WidthMismatch {
+                name,
+                expected,
+                found,
+            } => write!(
+                f,
+                "input {:?} has {} bits, expected {}",
+                name, found, expected
+            ),
+        }
+    }
+}
+
+impl std::error::Error for BristolError {}
+
+impl Circuit {
+    /// Parse a circuit in Bristol format
+    pub fn parse(s: &str) -> Result<Circuit, BristolError> {
+        // the numbered non-empty lines
+        let mut lines = s
+            .lines()
+            .enumerate()
+            .map(|(i, l)| (i + 1, l.split_whitespace().collect::<Vec<_>>()))
+            .filter(|(_, tokens)| !tokens.is_empty());
+
+        let mut header = || -> Result<Vec<usize>, BristolError> {
+            let (line, tokens) = lines.next().ok_or(BristolError::UnexpectedEnd)?;
+            tokens.iter().map(|t| number(line, t)).collect()
+        };
+        let counts = header()?;
+        let inputs = header()?;
+        let outputs = header()?;
+        let (gate_count, wire_count) = match counts[..] {
+            [gates, wires] => (gates, wires),
+            _ => return Err(BristolError::InvalidHeader),
+        };
+        let input_widths = widths(&inputs)?;
+        let output_widths = widths(&outputs)?;
+
+        // every gate is on a line of its own, and all wires but a few
+        // unused ones are read or written by a gate, so larger counts
+        // are not plausible for the length of the text. This also
+        // bounds what we allocate for them
+        if gate_count > s.len() || wire_count > s.len() {
+            return Err(BristolError::InvalidHeader);
+        }
+        let input_count = total(&input_widths)?;
+        let output_count = total(&output_widths)?;
+        if total(&[input_count, output_count])? > wire_count {
+            return Err(BristolError::InvalidHeader);
+        }
+
+        let mut assigned = vec![false; wire_count];
+        assigned[..input_count].fill(true);
+
+        let mut gates = Vec::new();
+        for (line, tokens) in lines {
+            let gate = parse_gate(line, &tokens)?;
+            for wire in gate.inputs() {
+                if !assigned.get(wire).copied().unwrap_or(false) {
+                    return Err(BristolError::UnassignedWire { line, wire });
+                }
+            }
+            let out = gate.output();
+            if out >= wire_count || assigned[out] {
+                return Err(BristolError::InvalidOutput { line, wire: out });
+            }
+            assigned[out] = true;
+            gates.push(gate);
+        }
+        if gates.len() < gate_count {
+            return Err(BristolError::UnexpectedEnd);
+        }
+        if gates.len() > gate_count || assigned[wire_count - output_count..].contains(&false) {
+            return Err(BristolError::InvalidHeader);
+        }
+
+        Ok(Circuit {
+            wire_count,
+            input_widths,
+            output_widths,
+            gates,
+        })
+    }
+
+    /// Evaluate the circuit on the inputs of the parties,
+    /// returning the bits of every output
+    pub fn evaluate(&self, inputs: &[Vec<bool>]) -> Result<Vec<Vec<bool>>, BristolError> {
+        if inputs.len() != self.input_widths.len() {
+            return Err(BristolError::InputMismatch {
+                index: inputs.len().min(self.input_widths.len()),
+            });
+        }
+        let mut wires = Vec::with_capacity(self.wire_count);
+        for (index, (input, width)) in inputs.iter().zip(&self.input_widths).enumerate() {
+            if input.len() != *width {
+                return Err(BristolError::InputMismatch { index });
+            }
+            wires.extend_from_slice(input);
+        }
+        wires.resize(self.wire_count, false);
+
+        for gate in &self.gates {
+            wires[gate.output()] = gate.eval(&wires);
+        }
+
+        let mut start = self.wire_count - self.output_widths.iter().sum::<usize>();
+        Ok(self
+            .output_widths
+            .iter()
+            .map(|width| {
+                let output = wires[start..start + width].to_vec();
+                start += width;
+                output
+            })
+            .collect())
+    }
+}
+
+impl Circuit {
+    /// The same circuit with only Bristol Fashion gates. An OR becomes
+    /// (a ^ b) ^ (a & b), which costs a single AND when garbled, and the
+    /// wires are renumbered so that the outputs remain the last ones
+    pub fn to_fashion(&self) -> Circuit {
+        let input_count: usize = self.input_widths.iter().sum();
+        let output_count: usize = self.output_widths.iter().sum();
+        let first_output = self.wire_count - output_count;
+
+        // all wires but the outputs are numbered in the order they are written
+        let temps = 2 * self
+            .gates
+            .iter()
+            .filter(|g| matches!(g, Gate::Or { .. }))
+            .count();
+        let wire_count = input_count + self.gates.len() + temps;
+        let mut map: Vec<usize> = (0..self.wire_count).collect();
+        let mut next = input_count;
+        let fresh = |next: &mut usize| {
+            *next += 1;
+            *next - 1
+        };
+
+        let mut gates = Vec::with_capacity(self.gates.len() + temps);
+        for gate in &self.gates {
+            let old = gate.output();
+            map[old] = if old >= first_output {
+                wire_count - output_count + (old - first_output)
+            } else {
+                fresh(&mut next)
+            };
+            let out = map[old];
+            let lowered = match *gate {
+                Gate::Xor { a, b, .. } => Gate::Xor {
+                    a: map[a],
+                    b: map[b],
+                    out,
+                },
+                Gate::And { a, b, .. } => Gate::And {
+                    a: map[a],
+                    b: map[b],
+                    out,
+                },
+                Gate::Or { a, b, .. } => {
+                    let (a, b) = (map[a], map[b]);
+                    let (x, y) = (fresh(&mut next), fresh(&mut next));
+                    gates.push(Gate::Xor { a, b, out: x });
+                    gates.push(Gate::And { a, b, out: y });
+                    Gate::Xor { a: x, b: y, out }
+                }
+                Gate::Not { a, .. } => Gate::Not { a: map[a], out },
+                Gate::Const { value, .. } => Gate::Const { value, out },
+                Gate::Copy { a, .. } => Gate::Copy { a: map[a], out },
+            };
+            gates.push(lowered);
+        }
+
+        Circuit {
+            wire_count,
+            input_widths: self.input_widths.clone(),
+            output_widths: self.output_widths.clone(),
+            gates,
+        }
+    }
+}
+
+impl fmt::Display for Circuit {
+    /// Write the circuit in the format read by parse
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{} {}", self.gates.len(), self.wire_count)?;
+        for widths in [&self.input_widths, &self.output_widths] {
+            write!(f, "{}", widths.len())?;
+            for width in widths {
+                write!(f, " {}", width)?;
+            }
+            writeln!(f)?;
+        }
+        writeln!(f)?;
+        for gate in &self.gates {
+            writeln!(f, "{}", gate)?;
+        }
+        Ok(())
+    }
+}
+
+/// The names of the inputs and outputs of a circuit, stored as JSON
+/// next to it. The wires of an input or output are start..start + width
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct CircuitInfo {
+    pub inputs: Vec<InputInfo>,
+    pub outputs: Vec<OutputInfo>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct InputInfo {
+    pub name: String,
+
+    /// the party that holds the input, 0 for the garbler and 1 for the evaluator
+    pub party: usize,
+    pub start: usize,
+    pub width: usize,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+#[cfg_attr(feature = "json", derive(Serialize, Deserialize))]
+pub struct OutputInfo {
+    pub name: String,
+    pub start: usize,
+    pub width: usize,
+}
+
+impl CircuitInfo {
+    /// Name the inputs and outputs of the circuit in order.
+    /// inputs holds the name and the party of each input.
+    /// The names must be unique and the inputs of the garbler
+    /// must come before the ones of the evaluator
+    pub fn new(
+        circuit: &Circuit,
+        inputs: &[(&str, usize)],
+        outputs: &[&str],
+    ) -> Result<CircuitInfo, BristolError> {
+        if inputs.len() != circuit.input_widths.len()
+            || outputs.len() != circuit.output_widths.len()
+        {
+            return Err(BristolError::InvalidInfo);
+        }
+        if inputs.iter().any(|(_, party)| *party > 1) || inputs.windows(2).any(|w| w[0].1 > w[1].1)
+        {
+            return Err(BristolError::InvalidInfo);
+        }
+        let names: Vec<&str> = inputs
+            .iter()
+            .map(|(name, _)| *name)
+            .chain(outputs.iter().copied())
+            .collect();
+        if names
+            .iter()
+            .enumerate()
+            .any(|(i, name)| names[..i].contains(name))
+        {
+            return Err(BristolError::InvalidInfo);
+        }
+        let mut start = 0;
+        let inputs = inputs
+            .iter()
+            .zip(&circuit.input_widths)
+            .map(|((name, party), width)| {
+                start += width;
+                InputInfo {
+                    name: name.to_string(),
+                    party: *party,
+                    start: start - width,
+                    width: *width,
+                }
+            })
+            .collect();
+        let mut start = circuit.wire_count - circuit.output_widths.iter().sum::<usize>();
+        let outputs = outputs
+            .iter()
+            .zip(&circuit.output_widths)
+            .map(|(name, width)| {
+                start += width;
+                OutputInfo {
+                    name: name.to_string(),
+                    start: start - width,
+                    width: *width,
+                }
+            })
+            .collect();
+        Ok(CircuitInfo { inputs, outputs })
+    }
+
+    #[cfg(feature = "json")]
+    pub fn from_json(s: &str) -> Result<CircuitInfo, BristolError> {
+        serde_json::from_str(s).map_err(|e| BristolError::Json(e.to_string()))
+    }
+
+    #[cfg(feature = "json")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap()
+    }
+
+    /// Check that the sidecar belongs to the circuit
+    pub fn check(&self, circuit: &Circuit) -> Result<(), BristolError> {
+        let names: Vec<(&str, usize)> = self
+            .inputs
+            .iter()
+            .map(|i| (i.name.as_str(), i.party))
+            .collect();
+        let outputs: Vec<&str> = self.outputs.iter().map(|o| o.name.as_str()).collect();
+        if CircuitInfo::new(circuit, &names, &outputs)? != *self {
+            return Err(BristolError::InvalidInfo);
+        }
+        Ok(())
+    }
+
+    pub fn input(&self, name: &str) -> Result<&InputInfo, BristolError> {
+        self.inputs
+            .iter()
+            .find(|i| i.name == name)
+            .ok_or_else(|| BristolError::UnknownName {
+                name: name.to_string(),
+            })
+    }
+
+    pub fn output(&self, name: &str) -> Result<&OutputInfo, BristolError> {
+        self.outputs
+            .iter()
+            .find(|o| o.name == name)
+            .ok_or_else(|| BristolError::UnknownName {
+                name: name.to_string(),
+            })
+    }
+
+    /// The input bits of the party in the order of the wires, from the
+    /// values of all its inputs given by name. With the inputs of the
+    /// garbler before the ones of the evaluator, these are the bits for
+    /// the garbler and the evaluator in the trinity module
+    pub fn encode(
+        &self,
+        party: usize,
+        values: &[(&str, &[bool])],
+    ) -> Result<Vec<bool>, BristolError> {
+        for (name, _) in values {
+            if self.input(name)?.party != party {
+                return Err(BristolError::UnknownName {
+                    name: name.to_string(),
+                });
+            }
+        }
+        let mut bits = Vec::new();
+        for input in self.inputs.iter().filter(|i| i.party == party) {
+            let (_, value) = values
+                .iter()
+                .find(|(name, _)| *name == input.name)
+                .ok_or_else(|| BristolError::MissingInput {
+                    name: input.name.clone(),
+                })?;
+            if value.len() != input.width {
+                return Err(BristolError::WidthMismatch {
+                    name: input.name.clone(),
+                    expected: input.width,
+                    found: value.len(),
+                });
+            }
+            bits.extend_from_slice(value);
+        }
+        Ok(bits)
+    }
+}
+
+/// Parse a string of 0s and 1s as used by the app, e.g. in test/HIRING.md
+pub fn parse_bits(s: &str) -> Option<Vec<bool>> {
+    s.chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| match c {
+            '0' => Some(false),
+            '1' => Some(true),
+            _ => None,
+        })
+        .collect()
+}
+
+fn number(line: usize, token: &str) -> Result<usize, BristolError> {
+    token.parse().map_err(|_| BristolError::InvalidNumber {
+        line,
+        token: token.to_string(),
+    })
+}
+
+// "<count> <width 0> <width 1> ..."
+fn widths(header: &[usize]) -> Result<Vec<usize>, BristolError> {
+    match header.split_first() {
+        Some((count, widths)) if *count == widths.len() => Ok(widths.to_vec()),
+        _ => Err(BristolError::InvalidHeader),
+    }
+}
+
+/// the sum of the widths, which may overflow for untrusted headers
+fn total(widths: &[usize]) -> Result<usize, BristolError> {
+    widths
+        .iter()
+        .try_fold(0usize, |sum, w| sum.checked_add(*w))
+        .ok_or(BristolError::InvalidHeader)
+}
+
+fn parse_gate(line: usize, tokens: &[&str]) -> Result<Gate, BristolError> {
+    let (op, wires) = tokens.split_last().ok_or(BristolError::UnexpectedEnd)?;
+    let wires = wires
+        .iter()
+        .map(|t| number(line, t))
+        .collect::<Result<Vec<_>, _>>()?;
+
+    let arity = BristolError::InvalidArity { line };
+    let (fan_in, fan_out) = match wires[..] {
+        [fan_in, fan_out, ..] => (fan_in, fan_out),
+        _ => return Err(arity),
+    };
+    // fan_in is untrusted, so compare without adding to it
+    if fan_out != 1 || wires.len().checked_sub(3) != Some(fan_in) {
+        return Err(arity);
+    }
+    let out = wires[2 + fan_in];
+    match (*op, &wires[2..2 + fan_in]) {
+        ("XOR", &[a, b]) => Ok(Gate::Xor { a, b, out }),
+        ("AND", &[a, b]) => Ok(Gate::And { a, b, out }),
+        ("OR", &[a, b]) => Ok(Gate::Or { a, b, out }),
+        ("INV" | "NOT", &[a]) => Ok(Gate::Not { a, out }),
+        ("EQ", &[value]) if value <= 1 => Ok(Gate::Const {
+            value: value == 1,
+            out,
+        }),
+        ("EQW", &[a]) => Ok(Gate::Copy { a, out }),
+        ("XOR" | "AND" | "OR" | "INV" | "NOT" | "EQ" | "EQW", _) => Err(arity),
+        _ => Err(BristolError::UnknownGate {
+            line,
+            op: op.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{parse_bits, BristolError, Circuit, CircuitInfo, Gate};
+
+    // the circuit used by the app, generated by hiring_original
+    const JOB_MATCHING: &str = include_str!("../fixtures/job_matching.txt");
+
+    // the same circuit in Bristol Fashion and the names of its inputs
+    #[cfg(feature = "json")]
+    const JOB_MATCHING_FASHION: &str = include_str!("../fixtures/job_matching_fashion.txt");
+    #[cfg(feature = "json")]
+    const JOB_MATCHING_INFO: &str = include_str!("../fixtures/job_matching_fashion.json");
+
+    // split the concatenated bits of both parties into the inputs of the circuit
+    fn eval_job_matching(circuit: &Circuit, a: &str, b: &str) -> bool {
+        let mut bits = parse_bits(a).unwrap();
+        bits.extend(parse_bits(b).unwrap());
+        let mut rest = &bits[..];
+        let inputs: Vec<_> = circuit
+            .input_widths
+            .iter()
+            .map(|width| {
+                let (input, tail) = rest.split_at(*width);
+                rest = tail;
+                input.to_vec()
+            })
+            .collect();
+        circuit.evaluate(&inputs).unwrap()[0][0]
+    }
+
+    #[test]
+    fn test_parse() {
+        let circuit =
+            Circuit::parse("3 7\n2 2 1\n1 1\n\n2 1 0 1 3 AND\n1 1 2 4 INV\n2 1 3 4 6 XOR\n")
+                .unwrap();
+        assert_eq!(circuit.input_widths, vec![2, 1]);
+        assert_eq!(circuit.output_widths, vec![1]);
+        assert_eq!(
+            circuit.gates,
+            vec![
+                Gate::And { a: 0, b: 1, out: 3 },
+                Gate::Not { a: 2, out: 4 },
+                Gate::Xor { a: 3, b: 4, out: 6 },
+            ]
+        );
+
+        // (a0 & a1) ^ !b
+        for x in 0..8 {
+            let bits: Vec<bool> = (0..3).map(|i| x >> i & 1 == 1).collect();
+            let output = circuit
+                .evaluate(&[bits[..2].to_vec(), bits[2..].to_vec()])
+                .unwrap();
+            assert_eq!(output, vec![vec![(bits[0] & bits[1]) ^ !bits[2]]]);
+        }
+        assert_eq!(
+            circuit.evaluate(&[vec![true, true], vec![]]),
+            Err(BristolError::InputMismatch { index: 1 })
+        );
+        assert_eq!(
+            circuit.evaluate(&[vec![true, true]]),
+            Err(BristolError::InputMismatch { index: 1 })
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let parse = |gates: &str| Circuit::parse(&format!("2 5\n1 2\n1 1\n{}", gates)).err();
+        assert_eq!(parse("2 1 0 1 2 AND\n"), Some(BristolError::UnexpectedEnd));
+        assert_eq!(
+            parse("2 1 0 1 2 AND\n2 1 0 3 4 XOR\n"),
+            Some(BristolError::UnassignedWire { line: 5, wire: 3 })
+        );
+        assert_eq!(
+            parse("2 1 0 1 1 AND\n2 1 0 1 4 XOR\n"),
+            Some(BristolError::InvalidOutput { line: 4, wire: 1 })
+        );
+        assert_eq!(
+            parse("2 1 0 1 2 NAND\n2 1 0 1 4 XOR\n"),
+            Some(BristolError::UnknownGate {
+                line: 4,
+                op: "NAND".to_string()
+            })
+        );
+        assert_eq!(
+            parse("1 1 0 1 2 AND\n2 1 0 1 4 XOR\n"),
+            Some(BristolError::InvalidArity { line: 4 })
+        );
+        assert_eq!(
+            parse("18446744073709551615 1 XOR\n2 1 0 1 4 XOR\n"),
+            Some(BristolError::InvalidArity { line: 4 })
+        );
+        assert_eq!(
+            parse("2 1 0 x 2 AND\n2 1 0 1 4 XOR\n"),
+            Some(BristolError::InvalidNumber {
+                line: 4,
+                token: "x".to_string()
+            })
+        );
+        // the last wire is the output
+        assert_eq!(
+            parse("2 1 0 1 2 AND\n2 1 0 1 3 XOR\n"),
+            Some(BristolError::InvalidHeader)
+        );
+
+        // counts that do not fit the text, or whose sum overflows
+        for header in [
+            "1 18446744073709551615\n1 1\n1 1\n",
+            "18446744073709551615 3\n1 1\n1 1\n",
+            "1 3\n2 18446744073709551615 2\n1 1\n",
+        ] {
+            assert_eq!(
+                Circuit::parse(header).err(),
+                Some(BristolError::InvalidHeader)
+            );
+        }
+    }
+
+    #[test]
+    fn test_job_matching() {
+        let circuit = Circuit::parse(JOB_MATCHING).unwrap();
+        assert_eq!(circuit.input_widths.iter().sum::<usize>(), 60);
+
+        // the test cases of app/test/HIRING.md
+        let a = "1 0 1000 00000001 0001 0100 00110010";
+        let cases = [
+            ("0 0 1000 00000001 0001 0100 00101101", true),
+            ("0 0 1000 00000001 0001 0100 00110111", false),
+            ("0 0 0100 00000001 0001 0100 00101101", false),
+            ("0 0 1000 00000001 0010 0100 00101101", false),
+            ("0 0 1000 00000001 0001 0010 00101101", false),
+            ("0 0 1000 00010000 0001 0100 00101101", false),
+        ];
+        for (b, expected) in cases {
+            assert_eq!(eval_job_matching(&circuit, a, b), expected, "{}", b);
+        }
+
+        // both parties are candidates
+        let a = "0 0 1000 00000001 0001 0100 00110010";
+        assert!(!eval_job_matching(&circuit, a, cases[0].0));
+    }
+
+    #[test]
+    fn test_fashion() {
+        // c = a | b, d = !c, e = a & 1, f = copy of e, the outputs are c, d and f
+        let circuit = Circuit::parse(
+            "5 7\n2 1 1\n3 1 1 1\n\n\
+             2 1 0 1 4 OR\n1 1 4 5 NOT\n1 1 1 2 EQ\n2 1 0 2 3 AND\n1 1 3 6 EQW\n",
+        )
+        .unwrap();
+        assert_eq!(
+            circuit.gates[2],
+            Gate::Const {
+                value: true,
+                out: 2
+            }
+        );
+        assert_eq!(circuit.gates[4], Gate::Copy { a: 3, out: 6 });
+
+        // the OR takes two more wires
+        let fashion = circuit.to_fashion();
+        assert_eq!(fashion.wire_count, 9);
+        let text = fashion.to_string();
+        assert!(!text.contains(" OR") && !text.contains(" NOT"));
+        assert_eq!(Circuit::parse(&text), Ok(fashion.clone()));
+
+        for x in 0..4 {
+            let inputs = [vec![x & 1 == 1], vec![x & 2 == 2]];
+            let (a, b) = (inputs[0][0], inputs[1][0]);
+            let outputs = vec![vec![a | b], vec![!(a | b)], vec![a]];
+            assert_eq!(circuit.evaluate(&inputs), Ok(outputs.clone()));
+            assert_eq!(fashion.evaluate(&inputs), Ok(outputs));
+        }
+
+        // the constant has to be a bit
+        assert_eq!(
+            Circuit::parse("1 3\n1 1\n1 1\n1 1 2 2 EQ\n").err(),
+            Some(BristolError::InvalidArity { line: 4 })
+        );
+    }
+
+    #[test]
+    fn test_circuit_info_names() {
+        let circuit =
+            Circuit::parse("3 7\n2 2 1\n1 1\n\n2 1 0 1 3 AND\n1 1 2 4 INV\n2 1 3 4 6 XOR\n")
+                .unwrap();
+        let info = CircuitInfo::new(&circuit, &[("a", 0), ("b", 1)], &["c"]).unwrap();
+        assert_eq!(info.check(&circuit), Ok(()));
+        assert!(CircuitInfo::new(&circuit, &[("a", 0), ("b", 0)], &["c"]).is_ok());
+
+        // the parties must be 0 or 1 with the garbler first,
+        // and no two inputs or outputs can share a name
+        for (inputs, outputs) in [
+            ([("a", 0), ("b", 2)], ["c"]),
+            ([("a", 1), ("b", 0)], ["c"]),
+            ([("a", 0), ("a", 1)], ["c"]),
+            ([("a", 0), ("b", 1)], ["a"]),
+        ] {
+            assert_eq!(
+                CircuitInfo::new(&circuit, &inputs, &outputs),
+                Err(BristolError::InvalidInfo)
+            );
+        }
+
+        // a sidecar read from elsewhere is checked the same way
+        let mut invalid = info.clone();
+        invalid.inputs[1].party = 2;
+        assert_eq!(invalid.check(&circuit), Err(BristolError::InvalidInfo));
+        let mut invalid = info.clone();
+        invalid.inputs.swap(0, 1);
+        invalid.inputs[0].start = 0;
+        invalid.inputs[1].start = 1;
+        assert_eq!(invalid.check(&circuit), Err(BristolError::InvalidInfo));
+        let mut invalid = info;
+        invalid.outputs[0].name = "b".to_string();
+        assert_eq!(invalid.check(&circuit), Err(BristolError::InvalidInfo));
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    fn test_circuit_info() {
+        let circuit = Circuit::parse(JOB_MATCHING_FASHION).unwrap();
+        let info = CircuitInfo::from_json(JOB_MATCHING_INFO).unwrap();
+        assert_eq!(info.check(&circuit), Ok(()));
+        assert_eq!(CircuitInfo::from_json(&info.to_json()), Ok(info.clone()));
+
+        // the outputs of the original circuit are on other wires
+        let original = Circuit::parse(JOB_MATCHING).unwrap();
+        assert_eq!(info.check(&original), Err(BristolError::InvalidInfo));
+
+        // the fields of a party as laid out in app/test/HIRING.md
+        let fields = |prefix: &str, s: &str| {
+            let bits = parse_bits(s).unwrap();
+            let mut fields = vec![
+                (format!("{}_position", prefix), bits[0..1].to_vec()),
+                (format!("{}_commitment", prefix), bits[1..2].to_vec()),
+                (format!("{}_salary", prefix), bits[22..30].to_vec()),
+            ];
+            for (name, start, len) in [
+                ("education", 2, 4),
+                ("experience", 6, 8),
+                ("interests", 14, 4),
+                ("company_stage", 18, 4),
+            ] {
+                for i in 0..len {
+                    let bit = vec![bits[start + i]];
+                    fields.push((format!("{}_{}_{}", prefix, name, i), bit));
+                }
+            }
+            fields
+        };
+        let a = fields("a", "1 0 1000 00000001 0001 0100 00110010");
+        let b = fields("b", "0 0 1000 00000001 0001 0100 00101101");
+        let a: Vec<(&str, &[bool])> = a.iter().map(|(n, v)| (n.as_str(), &v[..])).collect();
+        let b: Vec<(&str, &[bool])> = b.iter().map(|(n, v)| (n.as_str(), &v[..])).collect();
+
+        // test case 1, a match
+        let mut bits = info.encode(0, &a).unwrap();
+        bits.extend(info.encode(1, &b).unwrap());
+        let inputs: Vec<Vec<bool>> = info
+            .inputs
+            .iter()
+            .map(|i| bits[i.start..i.start + i.width].to_vec())
+            .collect();
+        assert_eq!(circuit.evaluate(&inputs), Ok(vec![vec![true]]));
+        assert_eq!(info.input("b_salary").unwrap().start, 52);
+        assert_eq!(
+            info.output("match_result").unwrap().start,
+            circuit.wire_count - 1
+        );
+
+        assert_eq!(
+            info.encode(1, &a),
+            Err(BristolError::UnknownName {
+                name: "a_position".to_string()
+            })
+        );
+        assert_eq!(
+            info.encode(1, &b[1..]),
+            Err(BristolError::MissingInput {
+                name: "b_position".to_string()
+            })
+        );
+        assert_eq!(
+            info.encode(1, &[(b[0].0, &[true, false])]),
+            Err(BristolError::WidthMismatch {
+                name: "b_position".to_string(),
+                expected: 1,
+                found: 2
+            })
+        );
+        assert!(info.output("salary").is_err());
+    }
+}
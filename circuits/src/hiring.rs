@@ -3,6 +3,9 @@ use std::{cell::RefCell, rc::Rc};
 use boolify::{generate_bristol, BoolWire, CircuitOutput, IdGenerator, ValueWire};
 use bristol_circuit::BristolCircuit;
 
+use bristol::Circuit;
+use crate::optimize::optimize_and_report;
+
 const INPUT_SIZE: usize = 30;
 
 struct JobCriteria {
@@ -37,7 +40,8 @@ fn extract_multi_bit_value(
     result
 }
 
-pub fn hiring() {
+/// The hiring circuit on two 30 bit inputs, one per party
+pub fn hiring_circuit() -> BristolCircuit {
     let id_gen = IdGenerator::new_rc_refcell();
 
     // Create just two input wires - one for each party
@@ -121,10 +125,83 @@ pub fn hiring() {
 
     // Generate circuit
     let outputs = vec![CircuitOutput::new("match_result", result)];
-    let bristol_circuit = generate_bristol(&outputs);
-    println!("Number of gates: {}", bristol_circuit.gates.len());
+    generate_bristol(&outputs)
+}
 
-    // Write to file
+pub fn hiring() {
+    let bristol_circuit = hiring_circuit();
     let output = BristolCircuit::get_bristol_string(&bristol_circuit).unwrap();
-    std::fs::write("job_matching_two_input.txt", output).unwrap();
+    let circuit = optimize_and_report(&Circuit::parse(&output).unwrap());
+
+    // Write to file
+    std::fs::write("job_matching_two_input.txt", circuit.to_string()).unwrap();
+}
+
+#[cfg(test)]
+mod tests {
+    use bristol_circuit::BristolCircuit;
+
+    use super::{hiring_circuit, INPUT_SIZE};
+    use bristol::{parse_bits, Circuit};
+    use crate::optimize::{optimize, Stats};
+
+    // The input of a party in the order of the bits of the input wire,
+    // i.e. with the fields as in JobCriteria and the salary starting with
+    // the least significant bit. The first wire of an input is its most
+    // significant bit, so the wires hold the bits in reverse
+    fn input(fields: &str, salary: u8) -> Vec<bool> {
+        let mut bits = parse_bits(fields).unwrap();
+        bits.extend((0..8).map(|i| salary >> i & 1 == 1));
+        assert_eq!(bits.len(), INPUT_SIZE);
+        bits.reverse();
+        bits
+    }
+
+    #[test]
+    fn test_hiring() {
+        let bristol = BristolCircuit::get_bristol_string(&hiring_circuit()).unwrap();
+        let circuit = Circuit::parse(&bristol).unwrap();
+        assert_eq!(circuit.input_widths, vec![INPUT_SIZE, INPUT_SIZE]);
+        assert_eq!(circuit.output_widths, vec![1]);
+
+        // both the generated and the optimised circuit
+        let optimized = optimize(&circuit);
+        assert!(Stats::new(&optimized).tables() < Stats::new(&circuit).tables());
+
+        // position, commitment, education, experience, interests, company stage
+        let recruiter = "1 0 1000 00000001 0001 0100";
+        let candidate = "0 0 1000 00000001 0001 0100";
+        let matches = |a: &str, a_salary: u8, b: &str, b_salary: u8| {
+            let inputs = [input(a, a_salary), input(b, b_salary)];
+            let result = circuit.evaluate(&inputs).unwrap();
+            assert_eq!(optimized.evaluate(&inputs).unwrap(), result);
+            result[0][0]
+        };
+
+        // the offered salary has to exceed the expected one
+        assert!(matches(recruiter, 50, candidate, 45));
+        assert!(matches(recruiter, 128, candidate, 1));
+        assert!(!matches(recruiter, 45, candidate, 50));
+        assert!(!matches(recruiter, 50, candidate, 50));
+
+        // the first party has to be the only recruiter
+        assert!(!matches(candidate, 50, candidate, 45));
+        assert!(!matches(recruiter, 50, recruiter, 45));
+        assert!(!matches(candidate, 50, recruiter, 45));
+
+        // every category needs an overlap
+        for other in [
+            "0 0 0100 00000001 0001 0100",
+            "0 0 1000 00010000 0001 0100",
+            "0 0 1000 00000001 0010 0100",
+            "0 0 1000 00000001 0001 0010",
+        ] {
+            assert!(!matches(recruiter, 50, other, 45), "{}", other);
+        }
+
+        // a commitment of the recruiter needs one of the candidate
+        let committed = "1 1 1000 00000001 0001 0100";
+        assert!(!matches(committed, 50, candidate, 45));
+        assert!(matches(committed, 50, "0 1 1000 00000001 0001 0100", 45));
+    }
 }
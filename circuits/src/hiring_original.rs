@@ -3,6 +3,9 @@ use std::{cell::RefCell, rc::Rc};
 use boolify::{generate_bristol, BoolWire, CircuitOutput, IdGenerator, ValueWire};
 use bristol_circuit::BristolCircuit;
 
+use bristol::{Circuit, CircuitInfo};
+use crate::optimize::optimize_and_report;
+
 struct JobCriteria {
     position: ValueWire,
     commitment: ValueWire,
@@ -13,23 +16,35 @@ struct JobCriteria {
     salary: ValueWire,
 }
 
-fn generate_job_criteria(prefix: &str, id_gen: &Rc<RefCell<IdGenerator>>) -> JobCriteria {
+// The inputs appear in the circuit in the order they are created,
+// so their names are recorded along the way for the sidecar
+fn generate_job_criteria(
+    prefix: &str,
+    id_gen: &Rc<RefCell<IdGenerator>>,
+    names: &mut Vec<String>,
+) -> JobCriteria {
+    let mut input = |name: String, width: usize| {
+        let wire = ValueWire::new_input(&name, width, id_gen);
+        names.push(name);
+        wire
+    };
+
     JobCriteria {
-        position: ValueWire::new_input(&format!("{}_position", prefix), 1, id_gen),
-        commitment: ValueWire::new_input(&format!("{}_commitment", prefix), 1, id_gen),
+        position: input(format!("{}_position", prefix), 1),
+        commitment: input(format!("{}_commitment", prefix), 1),
         education: (0..4)
-            .map(|i| ValueWire::new_input(&format!("{}_education_{}", prefix, i), 1, id_gen))
+            .map(|i| input(format!("{}_education_{}", prefix, i), 1))
             .collect(),
         experience: (0..8)
-            .map(|i| ValueWire::new_input(&format!("{}_experience_{}", prefix, i), 1, id_gen))
+            .map(|i| input(format!("{}_experience_{}", prefix, i), 1))
             .collect(),
         interests: (0..4)
-            .map(|i| ValueWire::new_input(&format!("{}_interests_{}", prefix, i), 1, id_gen))
+            .map(|i| input(format!("{}_interests_{}", prefix, i), 1))
             .collect(),
         company_stage: (0..4)
-            .map(|i| ValueWire::new_input(&format!("{}_company_stage_{}", prefix, i), 1, id_gen))
+            .map(|i| input(format!("{}_company_stage_{}", prefix, i), 1))
             .collect(),
-        salary: ValueWire::new_input(&format!("{}_salary", prefix), 8, id_gen),
+        salary: input(format!("{}_salary", prefix), 8),
     }
 }
 
@@ -37,8 +52,10 @@ pub fn hiring() {
     let id_gen = IdGenerator::new_rc_refcell();
 
     // Generate inputs for both parties using the new structure
-    let a = generate_job_criteria("a", &id_gen);
-    let b = generate_job_criteria("b", &id_gen);
+    let mut names = Vec::new();
+    let a = generate_job_criteria("a", &id_gen, &mut names);
+    let garbler_inputs = names.len();
+    let b = generate_job_criteria("b", &id_gen, &mut names);
 
     // Implement the matching logic
     let compatible_pos = ValueWire::bit_xor(&a.position, &b.position);
@@ -97,9 +114,21 @@ pub fn hiring() {
     // Generate circuit
     let outputs = vec![CircuitOutput::new("match_result", result)];
     let bristol_circuit = generate_bristol(&outputs);
-    println!("Number of gates: {}", bristol_circuit.gates.len());
+    let output = BristolCircuit::get_bristol_string(&bristol_circuit).unwrap();
+    let circuit = optimize_and_report(&Circuit::parse(&output).unwrap());
 
     // Write to file
-    let output = BristolCircuit::get_bristol_string(&bristol_circuit).unwrap();
-    std::fs::write("job_matching.txt", output).unwrap();
+    std::fs::write("job_matching.txt", circuit.to_string()).unwrap();
+
+    // Bristol Fashion and the names of the inputs and outputs.
+    // a is the garbler (party 0), b the evaluator (party 1)
+    let circuit = circuit.to_fashion();
+    let inputs: Vec<(&str, usize)> = names
+        .iter()
+        .enumerate()
+        .map(|(i, name)| (name.as_str(), usize::from(i >= garbler_inputs)))
+        .collect();
+    let info = CircuitInfo::new(&circuit, &inputs, &["match_result"]).unwrap();
+    std::fs::write("job_matching_fashion.txt", circuit.to_string()).unwrap();
+    std::fs::write("job_matching_fashion.json", info.to_json()).unwrap();
 }
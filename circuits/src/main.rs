@@ -1,7 +1,49 @@
 mod demo;
 mod hiring;
 mod hiring_original;
+mod optimize;
 
+use bristol::{parse_bits, Circuit};
+
+// Without arguments, write the hiring circuit. With
+//   original
+// write the circuit used by the app, job_matching.txt, along with
+// its Bristol Fashion version and the names of its inputs. With
+//   eval <circuit file> <input bits>...
+// evaluate a Bristol circuit in plaintext, one bit string per input,
+// and with
+//   optimize <circuit file> <output file>
+// optimise a Bristol circuit, see optimize.rs
 fn main() {
-    hiring::hiring();
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    match args.split_first() {
+        Some((command, args)) if command == "eval" => eval(args),
+        Some((command, args)) if command == "optimize" => optimize(args),
+        Some((command, _)) if command == "original" => hiring_original::hiring(),
+        _ => hiring::hiring(),
+    }
+}
+
+fn eval(args: &[String]) {
+    let (path, inputs) = args
+        .split_first()
+        .expect("usage: eval <circuit file> <input bits>...");
+    let circuit = Circuit::parse(&std::fs::read_to_string(path).unwrap()).unwrap();
+    let inputs: Vec<Vec<bool>> = inputs
+        .iter()
+        .map(|s| parse_bits(s).expect("inputs are strings of 0s and 1s"))
+        .collect();
+    for output in circuit.evaluate(&inputs).unwrap() {
+        let bits: String = output.iter().map(|b| if *b { '1' } else { '0' }).collect();
+        println!("{}", bits);
+    }
+}
+
+fn optimize(args: &[String]) {
+    let [input, output] = args else {
+        panic!("usage: optimize <circuit file> <output file>");
+    };
+    let circuit = Circuit::parse(&std::fs::read_to_string(input).unwrap()).unwrap();
+    let optimized = optimize::optimize_and_report(&circuit);
+    std::fs::write(output, optimized.to_string()).unwrap();
 }
@@ -0,0 +1,582 @@
+use std::collections::HashMap;
+use std::fmt;
+
+use bristol::{Circuit, Gate};
+
+// Optimiser for the circuits written by generate_bristol. Every pass
+// rebuilds the circuit gate by gate and
+//
+// - folds constants and NOTs: a wire is tracked as a constant or as
+//   another wire, possibly negated, so NOT and copies become free and
+//   a & a, a ^ a, a | !a, ... disappear. Two negated inputs of an AND
+//   or OR are handled by De Morgan, other negations are materialized
+//   with (shared) NOT gates
+// - eliminates common subexpressions by hashing the gates
+// - rewrites (s & p) ^ (s & q) to s & (p ^ q), and the same for |,
+//   if both ANDs are used only there, which saves an AND each time
+// - rewrites x | y to x ^ y if x = 1 forces y = 0, as for the terms
+//   a > b and a == b of a comparator. This looks a few gates back
+// - removes gates that do not contribute to an output and numbers
+//   the remaining wires consecutively
+//
+// The passes are repeated until the number of ANDs and ORs, which
+// cost two ciphertexts each under half-gates, no longer decreases.
+// The inputs and outputs of the circuit are kept as they are
+
+/// The gate counts of a circuit
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub struct Stats {
+    pub wires: usize,
+    pub and: usize,
+    pub or: usize,
+    pub xor: usize,
+    pub not: usize,
+
+    /// constants and copies
+    pub other: usize,
+}
+
+impl Stats {
+    pub fn new(circuit: &Circuit) -> Stats {
+        let mut stats = Stats {
+            wires: circuit.wire_count,
+            ..Stats::default()
+        };
+        for gate in &circuit.gates {
+            match gate {
+                Gate::And { .. } => stats.and += 1,
+                Gate::Or { .. } => stats.or += 1,
+                Gate::Xor { .. } => stats.xor += 1,
+                Gate::Not { .. } => stats.not += 1,
+                Gate::Const { .. } | Gate::Copy { .. } => stats.other += 1,
+            }
+        }
+        stats
+    }
+
+    pub fn gates(&self) -> usize {
+        self.and + self.or + self.xor + self.not + self.other
+    }
+
+    /// the gates that need a garbled table
+    pub fn tables(&self) -> usize {
+        self.and + self.or
+    }
+}
+
+impl fmt::Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "{} gates ({} AND, {} OR, {} XOR, {} NOT, {} other) on {} wires",
+            self.gates(),
+            self.and,
+            self.or,
+            self.xor,
+            self.not,
+            self.other,
+            self.wires
+        )
+    }
+}
+
+/// Optimise the circuit, see the passes above
+pub fn optimize(circuit: &Circuit) -> Circuit {
+    let mut best = simplify(circuit);
+    loop {
+        let next = simplify(&best);
+        if Stats::new(&next).tables() >= Stats::new(&best).tables() {
+            return best;
+        }
+        best = next;
+    }
+}
+
+/// Optimise the circuit and print the statistics before and after
+pub fn optimize_and_report(circuit: &Circuit) -> Circuit {
+    let optimized = optimize(circuit);
+    println!("before: {}", Stats::new(circuit));
+    println!("after:  {}", Stats::new(&optimized));
+    optimized
+}
+
+/// A wire of the original circuit in terms of the rebuilt one
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Value {
+    Const(bool),
+
+    /// the wire, negated if the flag is set
+    Wire(usize, bool),
+}
+
+impl Value {
+    fn negate(self, negate: bool) -> Value {
+        match self {
+            Value::Const(c) => Value::Const(c ^ negate),
+            Value::Wire(w, n) => Value::Wire(w, n ^ negate),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+enum Op {
+    Xor,
+    And,
+    Or,
+}
+
+// Wires of the rebuilt circuit are the inputs followed
+// by the outputs of the gates, in the order of the gates
+struct Builder {
+    input_count: usize,
+    gates: Vec<Gate>,
+
+    /// how often the wire is read in the original circuit
+    uses: Vec<usize>,
+
+    /// the wire computed by a gate, for CSE
+    known: HashMap<(Op, usize, usize), usize>,
+    nots: HashMap<usize, usize>,
+    consts: [Option<usize>; 2],
+}
+
+impl Builder {
+    fn new(input_count: usize) -> Builder {
+        Builder {
+            input_count,
+            gates: Vec::new(),
+            uses: vec![0; input_count],
+            known: HashMap::new(),
+            nots: HashMap::new(),
+            consts: [None, None],
+        }
+    }
+
+    fn push(&mut self, gate: Gate) -> usize {
+        self.gates.push(gate);
+        self.uses.push(0);
+        self.input_count + self.gates.len() - 1
+    }
+
+    fn next_wire(&self) -> usize {
+        self.input_count + self.gates.len()
+    }
+
+    fn gate(&mut self, op: Op, a: usize, b: usize) -> usize {
+        let (a, b) = (a.min(b), a.max(b));
+        if let Some(out) = self.known.get(&(op, a, b)) {
+            return *out;
+        }
+        let out = self.next_wire();
+        self.push(match op {
+            Op::Xor => Gate::Xor { a, b, out },
+            Op::And => Gate::And { a, b, out },
+            Op::Or => Gate::Or { a, b, out },
+        });
+        self.known.insert((op, a, b), out);
+        out
+    }
+
+    /// a wire holding the value
+    fn wire(&mut self, value: Value) -> usize {
+        match value {
+            Value::Wire(w, false) => w,
+            Value::Wire(w, true) => match self.nots.get(&w) {
+                Some(out) => *out,
+                None => {
+                    let out = self.push(Gate::Not {
+                        a: w,
+                        out: self.next_wire(),
+                    });
+                    self.nots.insert(w, out);
+                    out
+                }
+            },
+            Value::Const(value) => match self.consts[usize::from(value)] {
+                Some(out) => out,
+                None => {
+                    let out = self.push(Gate::Const {
+                        value,
+                        out: self.next_wire(),
+                    });
+                    self.consts[usize::from(value)] = Some(out);
+                    out
+                }
+            },
+        }
+    }
+
+    /// Bits that are set whenever the value is 1, found by
+    /// descending into ANDs, NORs and NOTs
+    fn literals(&self, value: Value, depth: usize, literals: &mut HashMap<usize, bool>) {
+        if let Value::Wire(w, negated) = value {
+            if depth > 0 && w >= self.input_count {
+                match (self.gates[w - self.input_count], negated) {
+                    (Gate::And { a, b, .. }, false) | (Gate::Or { a, b, .. }, true) => {
+                        self.literals(Value::Wire(a, negated), depth - 1, literals);
+                        self.literals(Value::Wire(b, negated), depth - 1, literals);
+                        return;
+                    }
+                    (Gate::Not { a, .. }, _) => {
+                        self.literals(Value::Wire(a, !negated), depth - 1, literals);
+                        return;
+                    }
+                    _ => (),
+                }
+            }
+            literals.insert(w, !negated);
+        }
+    }
+
+    /// the bit on the wire if it follows from the known bits
+    fn eval(&self, w: usize, depth: usize, known: &HashMap<usize, bool>) -> Option<bool> {
+        if let Some(bit) = known.get(&w) {
+            return Some(*bit);
+        }
+        if depth == 0 || w < self.input_count {
+            return None;
+        }
+        let eval = |a| self.eval(a, depth - 1, known);
+        match self.gates[w - self.input_count] {
+            Gate::Xor { a, b, .. } => Some(eval(a)? ^ eval(b)?),
+            Gate::And { a, b, .. } => match (eval(a), eval(b)) {
+                (Some(false), _) | (_, Some(false)) => Some(false),
+                (Some(true), Some(true)) => Some(true),
+                _ => None,
+            },
+            Gate::Or { a, b, .. } => match (eval(a), eval(b)) {
+                (Some(true), _) | (_, Some(true)) => Some(true),
+                (Some(false), Some(false)) => Some(false),
+                _ => None,
+            },
+            Gate::Not { a, .. } => eval(a).map(|bit| !bit),
+            Gate::Const { value, .. } => Some(value),
+            Gate::Copy { a, .. } => eval(a),
+        }
+    }
+
+    /// whether x = 1 implies y = 0, so x | y = x ^ y
+    fn disjoint(&self, x: Value, y: Value) -> bool {
+        let mut known = HashMap::new();
+        self.literals(x, 4, &mut known);
+        let mut needed = HashMap::new();
+        self.literals(y, 4, &mut needed);
+        needed
+            .iter()
+            .any(|(w, bit)| self.eval(*w, 4, &known).is_some_and(|b| b != *bit))
+    }
+
+    /// the inputs of the AND computing the value,
+    /// if nothing else reads it
+    fn single_and(&self, value: Value) -> Option<(usize, usize)> {
+        match value {
+            Value::Wire(w, false) if w >= self.input_count && self.uses[w] == 1 => {
+                match self.gates[w - self.input_count] {
+                    Gate::And { a, b, .. } => Some((a, b)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// (s & p) op (s & q) = s & (p op q) for op = ^ and |
+    fn distribute(&mut self, op: Op, x: Value, y: Value) -> Option<Value> {
+        let (a, b) = (self.single_and(x)?, self.single_and(y)?);
+        let (s, p, q) = [(a.0, a.1, b.0, b.1), (a.0, a.1, b.1, b.0)]
+            .into_iter()
+            .chain([(a.1, a.0, b.0, b.1), (a.1, a.0, b.1, b.0)])
+            .find(|(s, _, t, _)| s == t)
+            .map(|(s, p, _, q)| (s, p, q))?;
+        let (p, q) = (Value::Wire(p, false), Value::Wire(q, false));
+        let inner = match op {
+            Op::Xor => self.xor(p, q),
+            _ => self.or(p, q),
+        };
+        Some(self.and(Value::Wire(s, false), inner))
+    }
+
+    fn xor(&mut self, x: Value, y: Value) -> Value {
+        match (x, y) {
+            (Value::Const(c), v) | (v, Value::Const(c)) => v.negate(c),
+            (Value::Wire(a, na), Value::Wire(b, nb)) if a == b => Value::Const(na ^ nb),
+            (Value::Wire(a, na), Value::Wire(b, nb)) => {
+                if let Some(v) = self.distribute(Op::Xor, x, y) {
+                    return v;
+                }
+                Value::Wire(self.gate(Op::Xor, a, b), na ^ nb)
+            }
+        }
+    }
+
+    fn and(&mut self, x: Value, y: Value) -> Value {
+        match (x, y) {
+            (Value::Const(false), _) | (_, Value::Const(false)) => Value::Const(false),
+            (Value::Const(true), v) | (v, Value::Const(true)) => v,
+            (Value::Wire(a, na), Value::Wire(b, nb)) if a == b => {
+                if na == nb {
+                    x
+                } else {
+                    Value::Const(false)
+                }
+            }
+            // !a & !b = !(a | b)
+            (Value::Wire(a, true), Value::Wire(b, true)) => {
+                Value::Wire(self.gate(Op::Or, a, b), true)
+            }
+            _ => {
+                let (a, b) = (self.wire(x), self.wire(y));
+                Value::Wire(self.gate(Op::And, a, b), false)
+            }
+        }
+    }
+
+    fn or(&mut self, x: Value, y: Value) -> Value {
+        match (x, y) {
+            (Value::Const(true), _) | (_, Value::Const(true)) => Value::Const(true),
+            (Value::Const(false), v) | (v, Value::Const(false)) => v,
+            (Value::Wire(a, na), Value::Wire(b, nb)) if a == b => {
+                if na == nb {
+                    x
+                } else {
+                    Value::Const(true)
+                }
+            }
+            // !a | !b = !(a & b)
+            (Value::Wire(a, true), Value::Wire(b, true)) => {
+                Value::Wire(self.gate(Op::And, a, b), true)
+            }
+            _ => {
+                if let Some(v) = self.distribute(Op::Or, x, y) {
+                    return v;
+                }
+                if self.disjoint(x, y) || self.disjoint(y, x) {
+                    return self.xor(x, y);
+                }
+                let (a, b) = (self.wire(x), self.wire(y));
+                Value::Wire(self.gate(Op::Or, a, b), false)
+            }
+        }
+    }
+}
+
+/// One pass over the circuit
+fn simplify(circuit: &Circuit) -> Circuit {
+    let input_count: usize = circuit.input_widths.iter().sum();
+    let output_count: usize = circuit.output_widths.iter().sum();
+    let first_output = circuit.wire_count - output_count;
+
+    let mut reads = vec![0; circuit.wire_count];
+    for gate in &circuit.gates {
+        for w in gate.inputs() {
+            reads[w] += 1;
+        }
+    }
+    // outputs are read by the evaluator
+    for r in &mut reads[first_output..] {
+        *r += 1;
+    }
+
+    let mut builder = Builder::new(input_count);
+    let mut values: Vec<Value> = (0..circuit.wire_count)
+        .map(|w| Value::Wire(w, false))
+        .collect();
+    builder.uses.copy_from_slice(&reads[..input_count]);
+    for gate in &circuit.gates {
+        let value = match *gate {
+            Gate::Xor { a, b, .. } => builder.xor(values[a], values[b]),
+            Gate::And { a, b, .. } => builder.and(values[a], values[b]),
+            Gate::Or { a, b, .. } => builder.or(values[a], values[b]),
+            Gate::Not { a, .. } => values[a].negate(true),
+            Gate::Const { value, .. } => Value::Const(value),
+            Gate::Copy { a, .. } => values[a],
+        };
+        if let Value::Wire(w, _) = value {
+            builder.uses[w] += reads[gate.output()];
+        }
+        values[gate.output()] = value;
+    }
+
+    // every output needs its own wire written by a gate
+    let mut outputs = Vec::with_capacity(output_count);
+    for value in &values[first_output..] {
+        let mut w = builder.wire(*value);
+        if w < input_count || outputs.contains(&w) {
+            w = builder.push(Gate::Copy {
+                a: w,
+                out: builder.next_wire(),
+            });
+        }
+        outputs.push(w);
+    }
+
+    compact(circuit, input_count, &builder.gates, &outputs)
+}
+
+/// Remove the gates that do not lead to an output and number the
+/// wires of the remaining ones consecutively, with the outputs last
+fn compact(circuit: &Circuit, input_count: usize, gates: &[Gate], outputs: &[usize]) -> Circuit {
+    let mut live = vec![false; input_count + gates.len()];
+    for w in outputs {
+        live[*w] = true;
+    }
+    for gate in gates.iter().rev() {
+        if live[gate.output()] {
+            for w in gate.inputs() {
+                live[w] = true;
+            }
+        }
+    }
+
+    let live_gates = gates.iter().filter(|g| live[g.output()]).count();
+    let wire_count = input_count + live_gates;
+    let mut map: Vec<usize> = (0..live.len()).collect();
+    let mut next = input_count;
+    for gate in gates.iter().filter(|g| live[g.output()]) {
+        if !outputs.contains(&gate.output()) {
+            map[gate.output()] = next;
+            next += 1;
+        }
+    }
+    for (i, w) in outputs.iter().enumerate() {
+        map[*w] = wire_count - outputs.len() + i;
+    }
+
+    let gates = gates
+        .iter()
+        .filter(|g| live[g.output()])
+        .map(|gate| {
+            let out = map[gate.output()];
+            match *gate {
+                Gate::Xor { a, b, .. } => Gate::Xor {
+                    a: map[a],
+                    b: map[b],
+                    out,
+                },
+                Gate::And { a, b, .. } => Gate::And {
+                    a: map[a],
+                    b: map[b],
+                    out,
+                },
+                Gate::Or { a, b, .. } => Gate::Or {
+                    a: map[a],
+                    b: map[b],
+                    out,
+                },
+                Gate::Not { a, .. } => Gate::Not { a: map[a], out },
+                Gate::Const { value, .. } => Gate::Const { value, out },
+                Gate::Copy { a, .. } => Gate::Copy { a: map[a], out },
+            }
+        })
+        .collect();
+
+    Circuit {
+        wire_count,
+        input_widths: circuit.input_widths.clone(),
+        output_widths: circuit.output_widths.clone(),
+        gates,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{optimize, Stats};
+    use bristol::Circuit;
+
+    // all assignments of the inputs, one bit per input
+    fn check_equivalent(a: &Circuit, b: &Circuit) {
+        let n = a.input_widths.len();
+        for x in 0..1u32 << n {
+            let inputs: Vec<Vec<bool>> = (0..n).map(|i| vec![x >> i & 1 == 1]).collect();
+            assert_eq!(a.evaluate(&inputs), b.evaluate(&inputs), "{:b}", x);
+        }
+    }
+
+    #[test]
+    fn test_fold() {
+        // (a ^ a) | b, !!c & 1, (b & c) ^ (c & b), a | !a
+        let circuit = Circuit::parse(
+            "11 15\n3 1 1 1\n4 1 1 1 1\n\n\
+             2 1 0 0 3 XOR\n2 1 3 1 11 OR\n1 1 2 4 NOT\n1 1 4 5 NOT\n1 1 1 6 EQ\n\
+             2 1 5 6 12 AND\n2 1 1 2 7 AND\n2 1 2 1 8 AND\n2 1 7 8 13 XOR\n\
+             1 1 0 9 NOT\n2 1 0 9 14 OR\n",
+        )
+        .unwrap();
+        let optimized = optimize(&circuit);
+        check_equivalent(&circuit, &optimized);
+
+        // b and c are copied, b & c ^ b & c is 0 and a | !a is 1
+        let stats = Stats::new(&optimized);
+        assert_eq!(stats.tables(), 0);
+        assert_eq!(stats.gates(), 4);
+        assert_eq!(optimized.wire_count, 7);
+    }
+
+    #[test]
+    fn test_distribute() {
+        // (a & b) ^ (c & a) = a & (b ^ c), (a & d) | (c & d) = d & (a | c)
+        let circuit = Circuit::parse(
+            "6 10\n4 1 1 1 1\n2 1 1\n\n\
+             2 1 0 1 4 AND\n2 1 2 0 5 AND\n2 1 4 5 8 XOR\n\
+             2 1 0 3 6 AND\n2 1 2 3 7 AND\n2 1 6 7 9 OR\n",
+        )
+        .unwrap();
+        let optimized = optimize(&circuit);
+        check_equivalent(&circuit, &optimized);
+        assert_eq!(Stats::new(&circuit).tables(), 5);
+        assert_eq!(Stats::new(&optimized).tables(), 3);
+
+        // (a & b) ^ (a & c) and (a & c) | (a & d) share a & c,
+        // rewriting either would add an AND
+        let circuit = Circuit::parse(
+            "5 9\n4 1 1 1 1\n2 1 1\n\n\
+             2 1 0 1 4 AND\n2 1 0 2 5 AND\n2 1 0 3 6 AND\n\
+             2 1 4 5 7 XOR\n2 1 5 6 8 OR\n",
+        )
+        .unwrap();
+        let optimized = optimize(&circuit);
+        check_equivalent(&circuit, &optimized);
+        assert_eq!(Stats::new(&optimized), Stats::new(&circuit));
+    }
+
+    #[test]
+    fn test_job_matching() {
+        // already optimised by hiring_original, and lowered to Bristol Fashion
+        for text in [
+            include_str!("../job_matching.txt"),
+            include_str!("../job_matching_fashion.txt"),
+        ] {
+            let circuit = Circuit::parse(text).unwrap();
+            let optimized = optimize(&circuit);
+            assert_eq!(optimized.input_widths, circuit.input_widths);
+            assert!(Stats::new(&optimized).tables() <= Stats::new(&circuit).tables());
+            check_random(&circuit, &optimized);
+        }
+    }
+
+    // pseudorandom inputs for larger circuits
+    fn check_random(a: &Circuit, b: &Circuit) {
+        let input_count: usize = a.input_widths.iter().sum();
+        let mut x = 1u64;
+        for _ in 0..256 {
+            let mut bits = Vec::new();
+            for _ in 0..input_count {
+                x ^= x << 13;
+                x ^= x >> 7;
+                x ^= x << 17;
+                bits.push(x & 1 == 1);
+            }
+            let mut rest = &bits[..];
+            let inputs: Vec<Vec<bool>> = a
+                .input_widths
+                .iter()
+                .map(|width| {
+                    let (input, tail) = rest.split_at(*width);
+                    rest = tail;
+                    input.to_vec()
+                })
+                .collect();
+            assert_eq!(a.evaluate(&inputs), b.evaluate(&inputs));
+        }
+    }
+}
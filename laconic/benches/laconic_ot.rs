@@ -1,5 +1,7 @@
-use ark_bls12_381::{Bls12_381, Fr};
-use ark_ec::{pairing::Pairing, Group};
+use ark_bls12_377::Bls12_377;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::pairing::Pairing;
 use ark_poly::Radix2EvaluationDomain;
 use ark_std::rand::Rng;
 use ark_std::test_rng;
@@ -9,9 +11,15 @@ use laconic_ot::{CommitmentKey, LaconicOTRecv, LaconicOTSender};
 const MIN_LOG_SIZE: usize = 3;
 const MAX_LOG_SIZE: usize = 10;
 
+type Domain<E> = Radix2EvaluationDomain<<E as Pairing>::ScalarField>;
+
 fn laconic_ot_benchmarks(c: &mut Criterion) {
-    let name = "laconic_ot";
+    curve_benchmarks::<Bls12_381>(c, "laconic_ot/bls12_381");
+    curve_benchmarks::<Bn254>(c, "laconic_ot/bn254");
+    curve_benchmarks::<Bls12_377>(c, "laconic_ot/bls12_377");
+}
 
+fn curve_benchmarks<E: Pairing>(c: &mut Criterion, name: &str) {
     let mut commit_benchmarks = c.benchmark_group(format!("{0}/commit", name));
     commit_benchmarks.sample_size(10);
     for log_len in MIN_LOG_SIZE..=MAX_LOG_SIZE {
@@ -28,11 +36,9 @@ fn laconic_ot_benchmarks(c: &mut Criterion) {
                 }
 
                 b.iter(|| {
-                    let ck =
-                        CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, num)
-                            .unwrap();
+                    let ck = CommitmentKey::<E, Domain<E>>::setup(rng, num).unwrap();
 
-                    let _sender = LaconicOTRecv::new(&ck, &bits);
+                    let _sender = LaconicOTRecv::new(&ck, &bits).unwrap();
                 })
             },
         );
@@ -49,38 +55,19 @@ fn laconic_ot_benchmarks(c: &mut Criterion) {
             bits.push(rng.gen_bool(0.5));
         }
 
-        let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, num).unwrap();
-        let recv = LaconicOTRecv::new(&ck, &bits);
+        let ck = CommitmentKey::<E, Domain<E>>::setup(rng, num).unwrap();
+        let recv = LaconicOTRecv::new(&ck, &bits).unwrap();
 
         let m0 = [0u8; 32];
         let m1 = [1u8; 32];
 
+        let msgs = vec![(m0, m1); num];
+
         send_benchmarks.bench_with_input(BenchmarkId::from_parameter(log_len), &log_len, |b, _| {
             b.iter(|| {
-                let sender = LaconicOTSender::new(&ck, recv.commitment());
-                // precompute pairing
-                let l0 = recv.commitment();
-                let l1 = recv.commitment() - ck.u[0];
-
-                // m0, m1
-                let com0 = Bls12_381::pairing(l0, ck.g2);
-                let com1 = Bls12_381::pairing(l1, ck.g2);
-
-                let mut com0_precomp = vec![(com0, -com0)];
-                let mut com1_precomp = vec![(com1, -com1)];
-                for _ in 1..381 {
-                    let com0_square = *com0_precomp.last().unwrap().0.clone().double_in_place();
-                    let com1_square = *com1_precomp.last().unwrap().0.clone().double_in_place();
-                    let com0_square_inv = *com0_precomp.last().unwrap().1.clone().double_in_place();
-                    let com1_square_inv = *com1_precomp.last().unwrap().1.clone().double_in_place();
-                    com0_precomp.push((com0_square, com0_square_inv));
-                    com1_precomp.push((com1_square, com1_square_inv));
-                }
-
-                for i in 0..num {
-                    let _msg =
-                        sender.send_precompute_naf(rng, i, m0, m1, &com0_precomp, &com1_precomp);
-                }
+                let mut sender = LaconicOTSender::new(&ck, recv.commitment());
+                sender.prepare();
+                let _msgs = sender.send_batch(rng, &msgs).unwrap();
             })
         });
     }
@@ -97,8 +84,8 @@ fn laconic_ot_benchmarks(c: &mut Criterion) {
             bits.push(rng.gen_bool(0.5));
         }
 
-        let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, num).unwrap();
-        let recv = LaconicOTRecv::new(&ck, &bits);
+        let ck = CommitmentKey::<E, Domain<E>>::setup(rng, num).unwrap();
+        let recv = LaconicOTRecv::new(&ck, &bits).unwrap();
 
         let m0 = [0u8; 32];
         let m1 = [1u8; 32];
@@ -106,12 +93,14 @@ fn laconic_ot_benchmarks(c: &mut Criterion) {
         let sender = LaconicOTSender::new(&ck, recv.commitment());
 
         // Simulate all sends
-        let msgs: Vec<_> = (0..num).map(|i| sender.send(rng, i, m0, m1)).collect();
+        let msgs: Vec<_> = (0..num)
+            .map(|i| sender.send(rng, i, &m0, &m1).unwrap())
+            .collect();
 
         recv_benchmarks.bench_with_input(BenchmarkId::from_parameter(log_len), &log_len, |b, _| {
             b.iter(|| {
-                for i in 0..num {
-                    let _res = recv.recv(i, msgs[i].clone());
+                for (i, msg) in msgs.iter().enumerate() {
+                    let _res = recv.recv(i, msg).unwrap();
                 }
             })
         });
@@ -0,0 +1,366 @@
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_ff::PrimeField;
+use ark_poly::EvaluationDomain;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{One, UniformRand, Zero};
+use rand::Rng;
+use std::ops::Mul;
+
+use crate::error::LaconicError;
+use crate::kzg_types::CommitmentKey;
+use crate::kzg_utils::check_powers;
+
+// This module implements an updatable powers-of-tau ceremony.
+// Every participant raises the current powers to their own secret s,
+// so that alpha is the product of all secrets. As long as a single
+// participant deletes their secret, nobody knows alpha.
+// This follows the construction of https://eprint.iacr.org/2017/1050.pdf
+//
+// The hiding part of the key needs powers of a second generator h1
+// whose discrete logarithm with respect to g1 is unknown. Taking
+// h1 = g1^{alpha^n} from the same transcript is not enough: a prover
+// knows that h1 = u[n] and X^n - 1 vanishes on the domain, so it could
+// open the commitment to anything. Hence, h1 is hashed to the curve
+// and its powers are updated along with the powers of g1
+
+/// Powers-of-tau transcript together with the chain of contributions
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct Ceremony<E: Pairing> {
+    /// random identifier of this ceremony, see transcript
+    pub id: [u8; 32],
+
+    /// length of messages the resulting commitment key supports
+    pub message_length: usize,
+
+    /// powers[i] = g1^{alpha^i}, i in 0..domain.size()
+    /// where g1 is the standard generator
+    pub powers: Vec<E::G1Affine>,
+
+    /// hat_powers[i] = h1^{alpha^i}, i in 0..domain.size()
+    /// where h1 is the hiding generator, see hiding_generator
+    pub hat_powers: Vec<E::G1Affine>,
+
+    /// r = g2^{alpha} where g2 is the standard generator
+    pub r: E::G2Affine,
+
+    /// all contributions so far, in order
+    pub contributions: Vec<CeremonyContribution<E>>,
+}
+
+/// Public record of a single contribution
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct CeremonyContribution<E: Pairing> {
+    /// g1^{alpha} after this contribution
+    pub alpha_g1: E::G1Affine,
+
+    /// public key of the contributor, g1^s and g2^s
+    pub s_g1: E::G1Affine,
+    pub s_g2: E::G2Affine,
+
+    /// Schnorr proof of knowledge of s:
+    /// commitment g1^k and response k + c*s
+    pub pok_com: E::G1Affine,
+    pub pok_resp: E::ScalarField,
+}
+
+/// Hash of the parameters of the ceremony. Every contribution is absorbed
+/// once it is made, so the state before contribution k covers the id,
+/// the domain and contributions 0..k
+fn transcript<E: Pairing>(ceremony: &Ceremony<E>) -> blake3::Hasher {
+    let mut hsh = blake3::Hasher::new();
+    hsh.update(b"laconic-ot ceremony");
+    hsh.update(&ceremony.id);
+    hsh.update(&(ceremony.message_length as u64).to_le_bytes());
+    hsh.update(&(ceremony.powers.len() as u64).to_le_bytes());
+    hsh
+}
+
+/// Fiat-Shamir challenge for the proof of knowledge. It is bound to the
+/// transcript so far, so that a contribution cannot be replayed in
+/// another ceremony or at another position of the same one
+fn challenge<E: Pairing>(
+    transcript: &blake3::Hasher,
+    s_g1: &E::G1Affine,
+    pok_com: &E::G1Affine,
+) -> E::ScalarField {
+    let mut hsh = transcript.clone();
+    s_g1.serialize_compressed(&mut hsh).unwrap();
+    pok_com.serialize_compressed(&mut hsh).unwrap();
+    let mut bytes = [0u8; 64];
+    hsh.finalize_xof().fill(&mut bytes);
+    E::ScalarField::from_le_bytes_mod_order(&bytes)
+}
+
+/// The generator h1 for the hiding part of ceremony keys. It is hashed
+/// to the curve by try-and-increment, so nobody knows its discrete
+/// logarithm with respect to g1
+pub fn hiding_generator<E: Pairing>() -> E::G1Affine {
+    let size = E::G1Affine::generator().compressed_size();
+    for counter in 0u64.. {
+        let mut hsh = blake3::Hasher::new();
+        hsh.update(b"laconic-ot hiding generator");
+        hsh.update(&counter.to_le_bytes());
+        let mut bytes = vec![0u8; size];
+        hsh.finalize_xof().fill(&mut bytes);
+        if let Some(p) = E::G1Affine::from_random_bytes(&bytes) {
+            let h = p.clear_cofactor();
+            if !h.is_zero() {
+                return h;
+            }
+        }
+    }
+    unreachable!()
+}
+
+impl<E: Pairing> Ceremony<E> {
+    /// Start a new ceremony for commitment keys with evaluation domain D.
+    /// Initially alpha = 1, so there has to be at least one contribution.
+    /// The ceremony gets a random id, so that contributions to it are
+    /// only valid here
+    pub fn new<D: EvaluationDomain<E::ScalarField>>(
+        message_length: usize,
+    ) -> Result<Self, LaconicError> {
+        if message_length < 1 {
+            return Err(LaconicError::DomainTooSmall { message_length });
+        }
+        let domain =
+            D::new(message_length.max(2)).ok_or(LaconicError::DomainTooSmall { message_length })?;
+        let mut id = [0u8; 32];
+        rand::thread_rng().fill(&mut id);
+        Ok(Ceremony {
+            id,
+            message_length,
+            powers: vec![E::G1Affine::generator(); domain.size()],
+            hat_powers: vec![hiding_generator::<E>(); domain.size()],
+            r: E::G2Affine::generator(),
+            contributions: Vec::new(),
+        })
+    }
+
+    /// Update the powers with a fresh secret and record the contribution.
+    /// Participants should verify the ceremony before contributing.
+    /// The secret is dropped at the end of this function
+    pub fn contribute<R: rand::Rng>(&mut self, rng: &mut R) -> &CeremonyContribution<E> {
+        let g1 = E::G1Affine::generator();
+        let g2 = E::G2Affine::generator();
+
+        let mut s = E::ScalarField::rand(rng);
+        while s.is_zero() {
+            s = E::ScalarField::rand(rng);
+        }
+
+        // powers[i] = powers[i]^{s^i}, same for hat_powers
+        let raise = |powers: &[E::G1Affine]| {
+            let mut curr = E::ScalarField::one();
+            let mut raised = Vec::with_capacity(powers.len());
+            for p in powers.iter() {
+                raised.push(p.mul(curr));
+                curr *= s;
+            }
+            E::G1::normalize_batch(&raised)
+        };
+        let mut hsh = transcript(self);
+        for c in &self.contributions {
+            c.serialize_compressed(&mut hsh).unwrap();
+        }
+        self.powers = raise(&self.powers);
+        self.hat_powers = raise(&self.hat_powers);
+        self.r = self.r.mul(s).into_affine();
+
+        // prove knowledge of s
+        let s_g1 = g1.mul(s).into_affine();
+        let k = E::ScalarField::rand(rng);
+        let pok_com = g1.mul(k).into_affine();
+        let c = challenge::<E>(&hsh, &s_g1, &pok_com);
+
+        self.contributions.push(CeremonyContribution {
+            alpha_g1: self.powers[1],
+            s_g1,
+            s_g2: g2.mul(s).into_affine(),
+            pok_com,
+            pok_resp: k + c * s,
+        });
+        self.contributions.last().unwrap()
+    }
+
+    /// Check the chain of contributions and that the
+    /// powers are well-formed with respect to the last one
+    pub fn verify<R: rand::Rng>(&self, rng: &mut R) -> Result<(), LaconicError> {
+        let g1 = E::G1Affine::generator();
+        let g2 = E::G2Affine::generator();
+
+        let mut prev = g1;
+        let mut hsh = transcript(self);
+        for (index, c) in self.contributions.iter().enumerate() {
+            let invalid = LaconicError::InvalidContribution { index };
+            if c.s_g1.is_zero() {
+                return Err(invalid);
+            }
+
+            // proof of knowledge of s: g1^resp = com * (g1^s)^c
+            let ch = challenge::<E>(&hsh, &c.s_g1, &c.pok_com);
+            if g1.mul(c.pok_resp) != c.pok_com.into_group() + c.s_g1.mul(ch) {
+                return Err(invalid);
+            }
+
+            // same s in both groups: e(g1^s, g2) = e(g1, g2^s)
+            // alpha was updated with s: e(alpha_new, g2) = e(alpha_prev, g2^s)
+            let s_ok = E::pairing(c.s_g1, g2) == E::pairing(g1, c.s_g2);
+            let alpha_ok = E::pairing(c.alpha_g1, g2) == E::pairing(prev, c.s_g2);
+            if !s_ok || !alpha_ok {
+                return Err(invalid);
+            }
+            prev = c.alpha_g1;
+            c.serialize_compressed(&mut hsh).unwrap();
+        }
+
+        // the powers belong to the last contribution,
+        // and the hat_powers use the same alpha
+        if self.powers.len() < 2
+            || self.hat_powers.len() != self.powers.len()
+            || self.powers[0] != g1
+            || self.powers[1] != prev
+            || self.hat_powers[0] != hiding_generator::<E>()
+            || E::pairing(self.powers[1], g2) != E::pairing(g1, self.r)
+            || !check_powers::<E, R>(rng, &self.powers, g2, self.r)
+            || !check_powers::<E, R>(rng, &self.hat_powers, g2, self.r)
+        {
+            return Err(LaconicError::InvalidPowers);
+        }
+        Ok(())
+    }
+
+    /// Verify the ceremony and derive the commitment key from it
+    pub fn commitment_key<D: EvaluationDomain<E::ScalarField>, R: rand::Rng>(
+        &self,
+        rng: &mut R,
+    ) -> Result<CommitmentKey<E, D>, LaconicError> {
+        if self.contributions.is_empty() {
+            return Err(LaconicError::NoContributions);
+        }
+        self.verify(rng)?;
+        CommitmentKey::from_hiding_powers_of_tau(
+            self.message_length,
+            &self.powers,
+            &self.hat_powers,
+            E::G2Affine::generator(),
+            self.r,
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Bls12_381;
+    use ark_ec::pairing::Pairing;
+    use ark_ec::AffineRepr;
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+    use ark_std::UniformRand;
+    use std::ops::Mul;
+
+    use super::{hiding_generator, Ceremony};
+    use crate::error::LaconicError;
+    use crate::{LaconicOTRecv, LaconicOTSender};
+
+    type F = <Bls12_381 as Pairing>::ScalarField;
+    type D = Radix2EvaluationDomain<F>;
+
+    /// test a ceremony between two parties
+    #[test]
+    fn test_ceremony() {
+        let mut rng = ark_std::test_rng();
+        let mut ceremony = Ceremony::<Bls12_381>::new::<D>(6).unwrap();
+
+        // no contributions yet
+        assert_eq!(
+            ceremony.commitment_key::<D, _>(&mut rng).err(),
+            Some(LaconicError::NoContributions)
+        );
+
+        // first party contributes and sends the transcript over
+        ceremony.contribute(&mut rng);
+        let mut bytes = Vec::new();
+        ceremony.serialize_compressed(&mut bytes).unwrap();
+
+        // second party checks and contributes
+        let mut ceremony = Ceremony::<Bls12_381>::deserialize_compressed(&bytes[..]).unwrap();
+        ceremony.verify(&mut rng).unwrap();
+        ceremony.contribute(&mut rng);
+
+        // both derive the same key from the final transcript
+        let ck = ceremony.commitment_key::<D, _>(&mut rng).unwrap();
+        assert_eq!(ck.message_length, 6);
+        assert_eq!(ck.u.len(), 8);
+        assert_eq!(ck.hat_u[0], hiding_generator::<Bls12_381>());
+        assert!(!ck.u.contains(&ck.hat_u[0]));
+
+        let receiver = LaconicOTRecv::new(&ck, &[true, false, true]).unwrap();
+        let sender = LaconicOTSender::new(&ck, receiver.commitment());
+        let msg = sender.send(&mut rng, 0, &[0u8; 16], &[1u8; 16]).unwrap();
+        assert_eq!(receiver.recv(0, &msg).unwrap(), vec![1u8; 16]);
+    }
+
+    /// test that manipulated transcripts are rejected
+    #[test]
+    fn test_ceremony_reject() {
+        let mut rng = ark_std::test_rng();
+        let mut ceremony = Ceremony::<Bls12_381>::new::<D>(4).unwrap();
+        ceremony.contribute(&mut rng);
+        ceremony.contribute(&mut rng);
+        ceremony.verify(&mut rng).unwrap();
+
+        // wrong proof of knowledge
+        let mut bad = ceremony.clone();
+        bad.contributions[1].pok_resp += F::from(1u64);
+        assert_eq!(
+            bad.verify(&mut rng),
+            Err(LaconicError::InvalidContribution { index: 1 })
+        );
+
+        // first contribution replayed in another ceremony
+        let mut other = Ceremony::<Bls12_381>::new::<D>(4).unwrap();
+        other.contributions = ceremony.contributions[..1].to_vec();
+        other.powers = ceremony.powers.clone();
+        other.hat_powers = ceremony.hat_powers.clone();
+        other.r = ceremony.r;
+        assert_eq!(
+            other.verify(&mut rng),
+            Err(LaconicError::InvalidContribution { index: 0 })
+        );
+
+        // contribution removed from the chain
+        let mut bad = ceremony.clone();
+        bad.contributions.remove(0);
+        assert_eq!(
+            bad.verify(&mut rng),
+            Err(LaconicError::InvalidContribution { index: 0 })
+        );
+
+        // inconsistent power
+        let mut bad = ceremony.clone();
+        bad.powers[3] = <Bls12_381 as Pairing>::G1Affine::rand(&mut rng);
+        assert_eq!(bad.verify(&mut rng), Err(LaconicError::InvalidPowers));
+
+        // hiding powers with a known discrete logarithm
+        let mut bad = ceremony.clone();
+        bad.hat_powers = bad
+            .powers
+            .iter()
+            .map(|p| p.mul(F::from(2u64)).into())
+            .collect();
+        assert_eq!(bad.verify(&mut rng), Err(LaconicError::InvalidPowers));
+
+        // hiding powers for another alpha
+        let mut bad = ceremony.clone();
+        bad.hat_powers[2] = bad.hat_powers[1];
+        assert_eq!(bad.verify(&mut rng), Err(LaconicError::InvalidPowers));
+
+        // powers that do not match the last contribution
+        let mut bad = ceremony.clone();
+        bad.powers = vec![<Bls12_381 as Pairing>::G1Affine::generator(); bad.powers.len()];
+        bad.r = <Bls12_381 as Pairing>::G2Affine::generator();
+        assert_eq!(bad.verify(&mut rng), Err(LaconicError::InvalidPowers));
+    }
+}
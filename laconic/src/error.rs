@@ -0,0 +1,230 @@
+use ark_serialize::SerializationError;
+use std::fmt;
+
+/// Errors returned by the laconic OT and KZG functions.
+/// None of these should ever be reached by honest parties,
+/// so they usually indicate malformed or malicious peer data
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub enum LaconicError {
+    /// the scalar field has no evaluation domain
+    /// that can hold a message of the given length
+    DomainTooSmall { message_length: usize },
+
+    /// the message has more elements than the
+    /// evaluation domain of the commitment key
+    MessageTooLong { length: usize, max: usize },
+
+    /// the index does not refer to a committed position
+    IndexOutOfRange { index: usize, length: usize },
+
+    /// the bytes do not encode a point on the curve
+    MalformedPoint,
+
+    /// the point is on the curve, but not in
+    /// the prime order subgroup
+    SubgroupCheckFailed,
+
+    /// (de)serialization of an object failed
+    Serialization(String),
+
+    /// the ciphertext was tampered with or
+    /// decrypted with the wrong key
+    AuthenticationFailed,
+
+    /// the object belongs to a different receiver commitment
+    CommitmentMismatch,
+
+    /// the powers-of-tau transcript is too short
+    /// for the requested message length
+    NotEnoughPowers { available: usize, required: usize },
+
+    /// the contribution with the given index does not
+    /// extend the previous state of the ceremony
+    InvalidContribution { index: usize },
+
+    /// the powers are not of the form g1^{alpha^i}
+    /// or do not match the rest of the setup
+    InvalidPowers,
+
+    /// nobody contributed to the ceremony yet,
+    /// so the secret alpha is known
+    NoContributions,
+
+    /// proofs for the commitment already revealed so many
+    /// evaluations that another one would not be zero-knowledge,
+    /// or there is no padding to hide an update of the commitment
+    PaddingExhausted { revealed: usize, padding: usize },
+
+    /// the opening has a nonzero masking value, but the
+    /// commitment it is for has no hiding part
+    InvalidOpening,
+
+    /// the given part of the commitment key is malformed
+    InvalidCommitmentKey(&'static str),
+
+    /// the commitment key has no hiding part, e.g. because it
+    /// was built from a plain powers-of-tau transcript
+    NotHiding,
+
+    /// the encoded object uses a version
+    /// of the wire format we do not support
+    UnsupportedVersion { version: u8 },
+
+    /// the encoded object is for a different curve
+    CurveMismatch { expected: u8, found: u8 },
+
+    /// the curve id is not one of the curves we support
+    UnsupportedCurve { id: u8 },
+
+    /// the encoded object was produced for
+    /// a commitment key with a different domain
+    DomainMismatch { expected: usize, found: usize },
+
+    /// the number of values of a 1-out-of-N OT
+    /// is not between 2 and MAX_VALUES
+    InvalidValueCount { n: usize },
+
+    /// the committed value is not smaller than n
+    ValueOutOfRange { value: u8, n: usize },
+
+    /// the index appears more than once in a multi-opening
+    DuplicateIndex { index: usize },
+
+    /// a gate of the constraint system does not hold at the row
+    ConstraintNotSatisfied { row: usize },
+
+    /// the number of input labels does not match
+    /// the number of input wires of the circuit
+    LabelCountMismatch { expected: usize, found: usize },
+
+    /// the garbled circuit was produced for a different circuit
+    CircuitMismatch,
+}
+
+impl fmt::Display for LaconicError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LaconicError::DomainTooSmall { message_length } => write!(
+                f,
+                "DomainTooSmall: no evaluation domain for message length {}",
+                message_length
+            ),
+            LaconicError::MessageTooLong { length, max } => write!(
+                f,
+                "MessageTooLong: message has {} elements, at most {} are supported",
+                length, max
+            ),
+            LaconicError::IndexOutOfRange { index, length } => write!(
+                f,
+                "IndexOutOfRange: index {} out of range for length {}",
+                index, length
+            ),
+            LaconicError::MalformedPoint => {
+                write!(f, "MalformedPoint: bytes do not encode a curve point")
+            }
+            LaconicError::SubgroupCheckFailed => {
+                write!(
+                    f,
+                    "SubgroupCheckFailed: point is not in the prime order subgroup"
+                )
+            }
+            LaconicError::Serialization(e) => write!(f, "Serialization: {}", e),
+            LaconicError::AuthenticationFailed => {
+                write!(
+                    f,
+                    "AuthenticationFailed: ciphertext could not be authenticated"
+                )
+            }
+            LaconicError::CommitmentMismatch => {
+                write!(
+                    f,
+                    "CommitmentMismatch: object belongs to a different commitment"
+                )
+            }
+            LaconicError::NotEnoughPowers {
+                available,
+                required,
+            } => write!(
+                f,
+                "NotEnoughPowers: transcript has {} powers, {} are required",
+                available, required
+            ),
+            LaconicError::InvalidContribution { index } => {
+                write!(f, "InvalidContribution: contribution {} is invalid", index)
+            }
+            LaconicError::InvalidPowers => {
+                write!(f, "InvalidPowers: powers are not well-formed")
+            }
+            LaconicError::NoContributions => {
+                write!(f, "NoContributions: ceremony has no contributions")
+            }
+            LaconicError::PaddingExhausted { revealed, padding } => write!(
+                f,
+                "PaddingExhausted: {} evaluations revealed with {} points of padding",
+                revealed, padding
+            ),
+            LaconicError::InvalidOpening => {
+                write!(f, "InvalidOpening: opening has a nonzero masking value")
+            }
+            LaconicError::InvalidCommitmentKey(part) => {
+                write!(f, "InvalidCommitmentKey: {} is malformed", part)
+            }
+            LaconicError::NotHiding => {
+                write!(f, "NotHiding: commitment key has no hiding part")
+            }
+            LaconicError::UnsupportedVersion { version } => {
+                write!(f, "UnsupportedVersion: unknown format version {}", version)
+            }
+            LaconicError::CurveMismatch { expected, found } => write!(
+                f,
+                "CurveMismatch: expected curve {}, got curve {}",
+                expected, found
+            ),
+            LaconicError::UnsupportedCurve { id } => {
+                write!(f, "UnsupportedCurve: unknown curve {}", id)
+            }
+            LaconicError::DomainMismatch { expected, found } => write!(
+                f,
+                "DomainMismatch: expected domain size {}, got {}",
+                expected, found
+            ),
+            LaconicError::InvalidValueCount { n } => {
+                write!(f, "InvalidValueCount: {} values are not supported", n)
+            }
+            LaconicError::ValueOutOfRange { value, n } => write!(
+                f,
+                "ValueOutOfRange: value {} is not smaller than {}",
+                value, n
+            ),
+            LaconicError::DuplicateIndex { index } => {
+                write!(f, "DuplicateIndex: index {} is opened twice", index)
+            }
+            LaconicError::ConstraintNotSatisfied { row } => {
+                write!(
+                    f,
+                    "ConstraintNotSatisfied: a gate at row {} does not hold",
+                    row
+                )
+            }
+            LaconicError::LabelCountMismatch { expected, found } => write!(
+                f,
+                "LabelCountMismatch: expected {} input labels, got {}",
+                expected, found
+            ),
+            LaconicError::CircuitMismatch => {
+                write!(
+                    f,
+                    "CircuitMismatch: garbled circuit belongs to a different circuit"
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for LaconicError {}
+
+impl From<SerializationError> for LaconicError {
+    fn from(e: SerializationError) -> Self {
+        LaconicError::Serialization(e.to_string())
+    }
+}
@@ -0,0 +1,332 @@
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+
+use crate::error::LaconicError;
+use crate::wire::{decode_plain, encode_plain, Kind};
+use bristol::{Circuit, Gate};
+
+// This module garbles Bristol circuits with free-XOR and half-gates
+// (Zahur, Rosulek, Evans, "Two Halves Make a Whole", 2015).
+// The garbler picks a random offset delta with its lowest bit set,
+// and every wire w gets the labels W_0 and W_1 = W_0 ^ delta.
+// The lowest bit of a label is its color, so the colors of the two
+// labels differ and the evaluator learns which row of a table to use
+// without learning the bit (point-and-permute).
+//
+// XOR gates are free, C_0 = A_0 ^ B_0. NOT gates are free as well,
+// the garbler sets C_0 = A_1 and the evaluator keeps the label.
+// Constant wires get the public label 0, which stands for the constant.
+// AND gates take two ciphertexts, one for each half gate, and OR gates
+// are garbled as !(!a & !b), which only swaps the labels of the inputs
+// and the output. Labels are hashed with BLAKE3 and the index of the
+// half gate as tweak.
+//
+// The evaluator decodes the outputs with the colors of their 0-labels.
+
+/// length of a wire label in bytes
+pub const LABEL_SIZE: usize = 16;
+
+/// The label of a wire, standing for a bit only the garbler knows
+pub type Label = [u8; LABEL_SIZE];
+
+/// A garbled circuit, sent to the evaluator together with the circuit
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct GarbledCircuit {
+    /// the two half gates of every AND and OR gate, in circuit order
+    tables: Vec<[Label; 2]>,
+
+    /// colors of the 0-labels of the output wires
+    decoding: Vec<bool>,
+}
+
+/// The secret of the garbler, mapping input bits to labels
+#[derive(Clone)]
+pub struct Encoding {
+    delta: Label,
+
+    /// 0-labels of the input wires
+    zeros: Vec<Label>,
+}
+
+/// Garble the circuit with fresh labels
+pub fn garble<R: rand::Rng>(rng: &mut R, circuit: &Circuit) -> (GarbledCircuit, Encoding) {
+    let mut delta: Label = rng.gen();
+    delta[0] |= 1;
+
+    let input_count: usize = circuit.input_widths.iter().sum();
+    let mut zeros = vec![[0u8; LABEL_SIZE]; circuit.wire_count];
+    for zero in &mut zeros[..input_count] {
+        *zero = rng.gen();
+    }
+
+    let mut tables = Vec::new();
+    for gate in &circuit.gates {
+        zeros[gate.output()] = match *gate {
+            Gate::Xor { a, b, .. } => xor(&zeros[a], &zeros[b]),
+            Gate::Not { a, .. } => xor(&zeros[a], &delta),
+            Gate::Const { value, .. } => {
+                if value {
+                    delta
+                } else {
+                    [0u8; LABEL_SIZE]
+                }
+            }
+            Gate::Copy { a, .. } => zeros[a],
+            Gate::And { a, b, .. } => {
+                let (zero, table) = garble_and(&zeros[a], &zeros[b], &delta, tables.len());
+                tables.push(table);
+                zero
+            }
+            Gate::Or { a, b, .. } => {
+                let (a, b) = (xor(&zeros[a], &delta), xor(&zeros[b], &delta));
+                let (zero, table) = garble_and(&a, &b, &delta, tables.len());
+                tables.push(table);
+                xor(&zero, &delta)
+            }
+        };
+    }
+
+    let decoding = output_wires(circuit).map(|w| color(&zeros[w])).collect();
+    zeros.truncate(input_count);
+    (
+        GarbledCircuit { tables, decoding },
+        Encoding { delta, zeros },
+    )
+}
+
+/// Evaluate a garbled circuit on one label per input wire,
+/// returning the bits of every output as in Circuit::evaluate
+pub fn evaluate(
+    circuit: &Circuit,
+    garbled: &GarbledCircuit,
+    inputs: &[Label],
+) -> Result<Vec<Vec<bool>>, LaconicError> {
+    let input_count: usize = circuit.input_widths.iter().sum();
+    if inputs.len() != input_count {
+        return Err(LaconicError::LabelCountMismatch {
+            expected: input_count,
+            found: inputs.len(),
+        });
+    }
+    let table_count = circuit
+        .gates
+        .iter()
+        .filter(|g| matches!(g, Gate::And { .. } | Gate::Or { .. }))
+        .count();
+    if garbled.tables.len() != table_count
+        || garbled.decoding.len() != circuit.output_widths.iter().sum::<usize>()
+    {
+        return Err(LaconicError::CircuitMismatch);
+    }
+
+    let mut labels = inputs.to_vec();
+    labels.resize(circuit.wire_count, [0u8; LABEL_SIZE]);
+    let mut tables = garbled.tables.iter().enumerate();
+    for gate in &circuit.gates {
+        labels[gate.output()] = match *gate {
+            Gate::Xor { a, b, .. } => xor(&labels[a], &labels[b]),
+            Gate::Not { a, .. } | Gate::Copy { a, .. } => labels[a],
+            Gate::Const { .. } => [0u8; LABEL_SIZE],
+            Gate::And { a, b, .. } | Gate::Or { a, b, .. } => {
+                let (j, table) = tables.next().unwrap();
+                eval_and(&labels[a], &labels[b], table, j)
+            }
+        };
+    }
+
+    let mut bits = output_wires(circuit)
+        .zip(&garbled.decoding)
+        .map(|(w, d)| color(&labels[w]) ^ d);
+    Ok(circuit
+        .output_widths
+        .iter()
+        .map(|width| bits.by_ref().take(*width).collect())
+        .collect())
+}
+
+impl Encoding {
+    /// The label of the bit on the ith input wire
+    pub fn label(&self, i: usize, bit: bool) -> Result<Label, LaconicError> {
+        let zero = self.zeros.get(i).ok_or(LaconicError::IndexOutOfRange {
+            index: i,
+            length: self.zeros.len(),
+        })?;
+        Ok(if bit { xor(zero, &self.delta) } else { *zero })
+    }
+
+    /// Both labels of the ith input wire, e.g. to send them with laconic OT
+    pub fn labels(&self, i: usize) -> Result<[Label; 2], LaconicError> {
+        Ok([self.label(i, false)?, self.label(i, true)?])
+    }
+
+    /// The labels of the bits of an input starting at the given wire
+    pub fn encode(&self, start: usize, bits: &[bool]) -> Result<Vec<Label>, LaconicError> {
+        bits.iter()
+            .enumerate()
+            .map(|(i, bit)| self.label(start + i, *bit))
+            .collect()
+    }
+}
+
+impl GarbledCircuit {
+    /// Encode the garbled circuit in the wire format. It does not
+    /// depend on a curve or a commitment key, see encode_plain
+    pub fn to_bytes(&self) -> Result<Vec<u8>, LaconicError> {
+        encode_plain(Kind::GarbledCircuit, self)
+    }
+
+    /// Decode a garbled circuit encoded with to_bytes
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LaconicError> {
+        decode_plain(Kind::GarbledCircuit, bytes)
+    }
+}
+
+/// Garble the AND of the wires with 0-labels a and b as the jth gate.
+/// Returns the 0-label of the output and the table
+fn garble_and(a: &Label, b: &Label, delta: &Label, j: usize) -> (Label, [Label; 2]) {
+    let (pa, pb) = (color(a), color(b));
+    let (ha0, ha1) = (hash(a, 2 * j), hash(&xor(a, delta), 2 * j));
+    let (hb0, hb1) = (hash(b, 2 * j + 1), hash(&xor(b, delta), 2 * j + 1));
+
+    // generator half gate, a & pb
+    let mut tg = xor(&ha0, &ha1);
+    if pb {
+        tg = xor(&tg, delta);
+    }
+    let wg = if pa { xor(&ha0, &tg) } else { ha0 };
+
+    // evaluator half gate, a & (b ^ pb)
+    let te = xor(&xor(&hb0, &hb1), a);
+    let we = if pb { xor(&hb0, &xor(&te, a)) } else { hb0 };
+
+    (xor(&wg, &we), [tg, te])
+}
+
+fn eval_and(a: &Label, b: &Label, table: &[Label; 2], j: usize) -> Label {
+    let [tg, te] = table;
+    let mut wg = hash(a, 2 * j);
+    if color(a) {
+        wg = xor(&wg, tg);
+    }
+    let mut we = hash(b, 2 * j + 1);
+    if color(b) {
+        we = xor(&we, &xor(te, a));
+    }
+    xor(&wg, &we)
+}
+
+fn hash(label: &Label, tweak: usize) -> Label {
+    let mut hsh = blake3::Hasher::new();
+    hsh.update(b"laconic-ot garbling");
+    hsh.update(label);
+    hsh.update(&(tweak as u64).to_le_bytes());
+    let mut out = [0u8; LABEL_SIZE];
+    out.copy_from_slice(&hsh.finalize().as_bytes()[..LABEL_SIZE]);
+    out
+}
+
+fn xor(a: &Label, b: &Label) -> Label {
+    std::array::from_fn(|i| a[i] ^ b[i])
+}
+
+fn color(label: &Label) -> bool {
+    label[0] & 1 == 1
+}
+
+// the outputs are the last wires of the circuit
+fn output_wires(circuit: &Circuit) -> std::ops::Range<usize> {
+    circuit.wire_count - circuit.output_widths.iter().sum::<usize>()..circuit.wire_count
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_std::test_rng;
+    use rand::Rng;
+
+    use super::{evaluate, garble, GarbledCircuit};
+    use crate::error::LaconicError;
+    use bristol::Circuit;
+
+    #[test]
+    fn test_gates() {
+        let rng = &mut test_rng();
+
+        // a & b, a | b, !a ^ (b & 1 ^ 0) and a copy of a & 1
+        let circuit = Circuit::parse(
+            "11 13\n2 1 1\n4 1 1 1 1\n\
+             1 1 0 2 INV\n2 1 0 1 3 OR\n1 1 1 4 EQ\n1 1 0 5 EQ\n2 1 4 1 6 AND\n\
+             2 1 6 5 7 XOR\n2 1 4 0 8 AND\n2 1 0 1 9 AND\n2 1 3 3 10 AND\n\
+             2 1 2 7 11 XOR\n1 1 8 12 EQW\n",
+        )
+        .unwrap();
+
+        for _ in 0..4 {
+            let (garbled, encoding) = garble(rng, &circuit);
+            for (a, b) in [(false, false), (false, true), (true, false), (true, true)] {
+                let labels = [encoding.label(0, a).unwrap(), encoding.label(1, b).unwrap()];
+                let outputs = evaluate(&circuit, &garbled, &labels).unwrap();
+                assert_eq!(
+                    outputs,
+                    vec![vec![a & b], vec![a | b], vec![!a ^ b], vec![a]]
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_job_matching() {
+        let rng = &mut test_rng();
+        let circuit =
+            Circuit::parse(include_str!("../../bristol/fixtures/job_matching.txt")).unwrap();
+        let input_count: usize = circuit.input_widths.iter().sum();
+
+        let (garbled, encoding) = garble(rng, &circuit);
+        let bytes = garbled.to_bytes().unwrap();
+        assert_eq!(crate::wire::curve_of(&bytes), Ok(crate::wire::NO_CURVE));
+        let garbled = GarbledCircuit::from_bytes(&bytes).unwrap();
+
+        // the garbled circuit computes the same as the plain one
+        for _ in 0..32 {
+            let bits: Vec<bool> = (0..input_count).map(|_| rng.gen()).collect();
+            let mut rest = &bits[..];
+            let inputs: Vec<_> = circuit
+                .input_widths
+                .iter()
+                .map(|width| {
+                    let (input, tail) = rest.split_at(*width);
+                    rest = tail;
+                    input.to_vec()
+                })
+                .collect();
+
+            let labels = encoding.encode(0, &bits).unwrap();
+            assert_eq!(
+                evaluate(&circuit, &garbled, &labels).unwrap(),
+                circuit.evaluate(&inputs).unwrap()
+            );
+        }
+
+        let labels = encoding.encode(0, &vec![false; input_count]).unwrap();
+        assert_eq!(
+            evaluate(&circuit, &garbled, &labels[1..]),
+            Err(LaconicError::LabelCountMismatch {
+                expected: input_count,
+                found: input_count - 1
+            })
+        );
+        assert_eq!(
+            encoding.label(input_count, false),
+            Err(LaconicError::IndexOutOfRange {
+                index: input_count,
+                length: input_count
+            })
+        );
+        let (other, _) = garble(
+            rng,
+            &Circuit::parse("1 3\n2 1 1\n1 1\n2 1 0 1 2 AND\n").unwrap(),
+        );
+        assert_eq!(
+            evaluate(&circuit, &other, &labels),
+            Err(LaconicError::CircuitMismatch)
+        );
+    }
+}
@@ -2,33 +2,39 @@ use ark_ec::pairing::Pairing;
 use ark_ec::CurveGroup;
 use ark_poly::EvaluationDomain;
 use ark_std::UniformRand;
-use ark_std::Zero;
-
-use std::ops::Mul;
 
+use crate::error::LaconicError;
+use crate::kzg_fk_open::{all_openings, precompute_y};
 use crate::kzg_types::Commitment;
-use crate::kzg_types::Opening;
 use crate::kzg_types::State;
+use crate::kzg_types::{CommitmentKey, VcKZG};
+use crate::kzg_types::{MultiOpening, Opening};
 use crate::kzg_utils::plain_kzg_com;
+use crate::kzg_utils::plain_kzg_verify_inside;
 use crate::kzg_utils::witness_evals_inside;
-use crate::{
-    kzg_fk_open::precompute_y,
-    kzg_types::{CommitmentKey, VcKZG},
-};
+use crate::kzg_utils::{check_indices, plain_kzg_multi_open, plain_kzg_multi_verify};
 
 impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> VcKZG<E, D> {
     pub fn setup<R: rand::Rng>(
         rng: &mut R,
         message_length: usize,
-    ) -> Result<CommitmentKey<E, D>, ()> {
+    ) -> Result<CommitmentKey<E, D>, LaconicError> {
         CommitmentKey::setup(rng, message_length)
     }
 
     pub fn commit<R: rand::Rng>(
         rng: &mut R,
         ck: &CommitmentKey<E, D>,
-        m: &Vec<E::ScalarField>,
-    ) -> (Commitment<E>, State<E>) {
+        m: &[E::ScalarField],
+    ) -> Result<(Commitment<E>, State<E>), LaconicError> {
+        if m.len() > ck.message_length {
+            return Err(LaconicError::MessageTooLong {
+                length: m.len(),
+                max: ck.message_length,
+            });
+        }
+        ck.h1()?;
+
         // evals[0..domain.size] will store evaluations of our polynomial
         // over our evaluation domain, namely
         // evals[i] = m[i]   if m[i] is defined,
@@ -39,10 +45,8 @@ impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> VcKZG<E, D> {
         // we can easily do a single MSM later
         let dsize = ck.domain.size();
         let mut evals = Vec::with_capacity(2 * dsize);
-        for i in 0..m.len() {
-            evals.push(m[i]);
-        }
-        for _ in m.len()..2 * ck.domain.size() {
+        evals.extend_from_slice(m);
+        for _ in m.len()..2 * dsize {
             evals.push(E::ScalarField::rand(rng));
         }
 
@@ -54,12 +58,19 @@ impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> VcKZG<E, D> {
             precomputed_v: None,
         };
         let com = Commitment { com_kzg };
-        (com, state)
+        Ok((com, state))
     }
 
-    pub fn open(ck: &CommitmentKey<E, D>, st: &State<E>, i: u32) -> Result<Opening<E>, ()> {
+    pub fn open(
+        ck: &CommitmentKey<E, D>,
+        st: &State<E>,
+        i: u32,
+    ) -> Result<Opening<E>, LaconicError> {
         if i as usize >= ck.message_length {
-            return Err(());
+            return Err(LaconicError::IndexOutOfRange {
+                index: i as usize,
+                length: ck.message_length,
+            });
         }
 
         // compute v: the KZG opening, which is a KZG commitment
@@ -77,9 +88,179 @@ impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> VcKZG<E, D> {
                 i as usize,
                 &mut witn_evals,
             );
-            plain_kzg_com(&ck, &witn_evals)
+            plain_kzg_com(ck, &witn_evals)
         };
 
-        Ok(Opening { v })
+        let hat_y = st.evals[ck.domain.size() + i as usize];
+        Ok(Opening { v, hat_y })
+    }
+
+    /// Verify that the opening proves that the ith
+    /// element of the vector committed in com is value
+    pub fn verify(
+        ck: &CommitmentKey<E, D>,
+        com: &Commitment<E>,
+        i: u32,
+        value: E::ScalarField,
+        opening: &Opening<E>,
+    ) -> bool {
+        if i as usize >= ck.message_length {
+            return false;
+        }
+        plain_kzg_verify_inside(ck, i as usize, &com.com_kzg, value, opening)
+    }
+
+    /// Open all given indices at once with a constant-size proof.
+    /// com must be the commitment belonging to the state
+    pub fn open_multi(
+        ck: &CommitmentKey<E, D>,
+        com: &Commitment<E>,
+        st: &State<E>,
+        indices: &[u32],
+    ) -> Result<MultiOpening<E>, LaconicError> {
+        let indices: Vec<usize> = indices.iter().map(|i| *i as usize).collect();
+        check_indices(&indices, ck.message_length)?;
+        Ok(plain_kzg_multi_open(ck, &com.com_kzg, &st.evals, &indices))
+    }
+
+    /// Verify that the opening proves that the element at
+    /// indices[k] of the vector committed in com is values[k]
+    pub fn verify_multi(
+        ck: &CommitmentKey<E, D>,
+        com: &Commitment<E>,
+        indices: &[u32],
+        values: &[E::ScalarField],
+        opening: &MultiOpening<E>,
+    ) -> bool {
+        let indices: Vec<usize> = indices.iter().map(|i| *i as usize).collect();
+        if check_indices(&indices, ck.message_length).is_err() {
+            return false;
+        }
+        plain_kzg_multi_verify(ck, &com.com_kzg, &indices, values, opening)
+    }
+
+    /// Compute the openings for all indices at once using the FK technique
+    /// and store them in the state, so that subsequent calls to open are cheap
+    pub fn precompute_all_openings(ck: &CommitmentKey<E, D>, st: &mut State<E>) {
+        let hat_y = precompute_y::<E, D>(&ck.hat_u, &ck.domain);
+        let vs = all_openings::<E, D>(&ck.y, &hat_y, &ck.domain, &st.evals);
+        st.precomputed_v = Some(vs);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Bls12_381;
+    use ark_ec::pairing::Pairing;
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::UniformRand;
+
+    use crate::error::LaconicError;
+    use crate::kzg_types::{MultiOpening, VcKZG};
+
+    type F = <Bls12_381 as Pairing>::ScalarField;
+    type D = Radix2EvaluationDomain<F>;
+    type Vc = VcKZG<Bls12_381, D>;
+
+    /// test functions open and verify
+    #[test]
+    fn test_open_verify() {
+        let mut rng = ark_std::test_rng();
+        let ck = Vc::setup(&mut rng, 6).unwrap();
+
+        let m: Vec<F> = (0..5).map(|_| F::rand(&mut rng)).collect();
+        let (com, st) = Vc::commit(&mut rng, &ck, &m).unwrap();
+
+        for (i, mi) in m.iter().enumerate() {
+            let op = Vc::open(&ck, &st, i as u32).unwrap();
+            assert!(Vc::verify(&ck, &com, i as u32, *mi, &op));
+
+            // binding: the opening does not verify for
+            // another value or at another index
+            assert!(!Vc::verify(&ck, &com, i as u32, *mi + F::from(1u64), &op));
+            assert!(!Vc::verify(&ck, &com, (i as u32 + 1) % 5, *mi, &op));
+        }
+
+        // indices up to the message length can be opened
+        let op = Vc::open(&ck, &st, 5).unwrap();
+        assert!(Vc::verify(&ck, &com, 5, st.evals[5], &op));
+        assert!(!Vc::verify(&ck, &com, 6, st.evals[6], &op));
+    }
+
+    /// test functions open_multi and verify_multi
+    #[test]
+    fn test_open_verify_multi() {
+        let mut rng = ark_std::test_rng();
+        let ck = Vc::setup(&mut rng, 8).unwrap();
+
+        let m: Vec<F> = (0..8).map(|_| F::rand(&mut rng)).collect();
+        let (com, st) = Vc::commit(&mut rng, &ck, &m).unwrap();
+
+        for indices in [vec![], vec![3], vec![0, 5, 2], (0..8).collect()] {
+            let values: Vec<F> = indices.iter().map(|i| m[*i as usize]).collect();
+            let op = Vc::open_multi(&ck, &com, &st, &indices).unwrap();
+            assert!(Vc::verify_multi(&ck, &com, &indices, &values, &op));
+
+            // constant size, independent of the subset
+            let bytes = op.to_bytes(&ck).unwrap();
+            assert_eq!(MultiOpening::from_bytes(&bytes, &ck), Ok(op.clone()));
+
+            if let Some(k) = indices.len().checked_sub(1) {
+                // binding: the opening does not verify for
+                // another value or another subset
+                let mut bad = values.clone();
+                bad[k] += F::from(1u64);
+                assert!(!Vc::verify_multi(&ck, &com, &indices, &bad, &op));
+                assert!(!Vc::verify_multi(
+                    &ck,
+                    &com,
+                    &indices[..k],
+                    &values[..k],
+                    &op
+                ));
+            }
+        }
+
+        // indices must be distinct and in range
+        assert_eq!(
+            Vc::open_multi(&ck, &com, &st, &[1, 4, 1]).err(),
+            Some(LaconicError::DuplicateIndex { index: 1 })
+        );
+        assert_eq!(
+            Vc::open_multi(&ck, &com, &st, &[8]).err(),
+            Some(LaconicError::IndexOutOfRange {
+                index: 8,
+                length: 8
+            })
+        );
+    }
+
+    /// test that commitments are hiding
+    #[test]
+    fn test_hiding() {
+        let mut rng = ark_std::test_rng();
+        let ck = Vc::setup(&mut rng, 4).unwrap();
+
+        // the same message gives different commitments
+        let m: Vec<F> = (0..4).map(|_| F::rand(&mut rng)).collect();
+        let (com0, st0) = Vc::commit(&mut rng, &ck, &m).unwrap();
+        let (com1, _) = Vc::commit(&mut rng, &ck, &m).unwrap();
+        assert_ne!(com0.com_kzg, com1.com_kzg);
+
+        // the masking polynomial is random, so the
+        // masking values in the openings look random
+        let op0 = Vc::open(&ck, &st0, 0).unwrap();
+        let op1 = Vc::open(&ck, &st0, 1).unwrap();
+        assert_ne!(op0.hat_y, op1.hat_y);
+        assert_ne!(op0.hat_y, F::from(0u64));
+        // binding: the openings do not verify for another
+        // value, or with another masking value
+        for (i, op) in [op0, op1].iter().enumerate() {
+            assert!(Vc::verify(&ck, &com0, i as u32, m[i], op));
+            assert!(!Vc::verify(&ck, &com0, i as u32, m[i] + F::from(1u64), op));
+            let mut bad = op.clone();
+            bad.hat_y += F::from(1u64);
+            assert!(!Vc::verify(&ck, &com0, i as u32, m[i], &bad));
+        }
     }
 }
@@ -0,0 +1,236 @@
+use ark_ec::pairing::Pairing;
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::Field;
+use ark_poly::univariate::DensePolynomial;
+use ark_poly::{DenseUVPolynomial, EvaluationDomain, Polynomial};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{One, UniformRand};
+use std::ops::Mul;
+
+use crate::error::LaconicError;
+use crate::kzg_types::{CommitmentKey, Opening};
+use crate::kzg_utils::{divide, fiat_shamir, kzg_com_coeffs, plain_kzg_verify, split_quotient};
+use crate::wire::{check_domain_size, decode, encode, CurveId, Kind};
+
+// This module implements a zero-knowledge proof that a KZG commitment
+// to f, given by its evaluations over the domain, holds only bits on
+// the first m points w_0, ..., w_{m-1} of the domain. This is the case
+// iff f(f - 1) vanishes on them, i.e., iff Z_S = prod_{i<m} (X - w_i)
+// divides f(f - 1). As in PLONK, the prover commits to the quotient
+// t = f(f - 1) / Z_S, which has degree up to 2n - 2 - m, split as
+// t = t_lo + X^{n-1} t_hi so that both parts fit the n powers of the key.
+// The identity f(f - 1) = Z_S * t is then checked at a random point zeta,
+// and the three evaluations are batched into a single KZG opening.
+// The proof reveals f(zeta), so the commitment needs at least two random
+// evaluations beyond the first m points for the proof to be zero-knowledge.
+// This holds for a single proof: every further proof for the same
+// commitment reveals f at another point and needs another random evaluation
+
+/// number of evaluations of f outside of the domain revealed by a proof
+pub const REVEALED_EVALUATIONS: usize = 1;
+
+/// Proof that the first m committed values are bits
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct BitsProof<E: Pairing> {
+    /// hiding commitments to the parts of the quotient
+    t_lo: E::G1Affine,
+    t_hi: E::G1Affine,
+
+    /// evaluations of f, t_lo and t_hi at zeta
+    y: E::ScalarField,
+    y_lo: E::ScalarField,
+    y_hi: E::ScalarField,
+
+    /// opening of f + gamma * t_lo + gamma^2 * t_hi at zeta
+    opening: Opening<E>,
+}
+
+/// Prove that evals[i] is a bit for all i < length.
+/// evals are the evaluations of the polynomial committed in com
+pub fn prove<E: Pairing, D: EvaluationDomain<E::ScalarField>, R: rand::Rng>(
+    rng: &mut R,
+    ck: &CommitmentKey<E, D>,
+    com: &E::G1Affine,
+    evals: &[E::ScalarField],
+    length: usize,
+) -> Result<BitsProof<E>, LaconicError> {
+    let n = ck.domain.size();
+    // two random evaluations hide one proof, repeated proofs
+    // have to be limited by the caller, see LaconicOTRecv::prove_bits
+    if length + 2 > n {
+        return Err(LaconicError::MessageTooLong { length, max: n - 2 });
+    }
+
+    // f(f - 1) in evaluation form over a domain of twice the size
+    let f = DensePolynomial::from_coefficients_vec(ck.domain.ifft(evals));
+    let domain2 = D::new(2 * n).unwrap();
+    let f_evals2 = domain2.fft(&f.coeffs);
+    let ff: Vec<_> = f_evals2
+        .iter()
+        .map(|v| *v * (*v - E::ScalarField::one()))
+        .collect();
+    let ff = DensePolynomial::from_coefficients_vec(domain2.ifft(&ff));
+    let t = divide::<E>(&ff, &vanishing_poly::<E, D>(&ck.domain, length));
+
+    let (lo, hi) = split_quotient::<E, R>(rng, &t, n);
+
+    // hide the commitments with constant masking polynomials
+    let r_lo = E::ScalarField::rand(rng);
+    let r_hi = E::ScalarField::rand(rng);
+    let h1 = ck.h1()?;
+    let t_lo = (kzg_com_coeffs::<E>(&ck.u, &lo) + h1.mul(r_lo)).into_affine();
+    let t_hi = (kzg_com_coeffs::<E>(&ck.u, &hi) + h1.mul(r_hi)).into_affine();
+
+    let zeta = challenge::<E>(&(*com, length as u64, t_lo, t_hi));
+    let y = f.evaluate(&zeta);
+    let y_lo = lo.evaluate(&zeta);
+    let y_hi = hi.evaluate(&zeta);
+    let gamma = challenge::<E>(&(zeta, y, y_lo, y_hi));
+
+    // open the combination at zeta, the masking parts are constant
+    let comb = &(&f + &(&lo * gamma)) + &(&hi * gamma.square());
+    let x_minus_zeta = DensePolynomial::from_coefficients_vec(vec![-zeta, E::ScalarField::one()]);
+    let psi = divide::<E>(&comb, &x_minus_zeta);
+    let opening = Opening {
+        v: kzg_com_coeffs::<E>(&ck.u, &psi).into_affine(),
+        hat_y: gamma * r_lo + gamma.square() * r_hi,
+    };
+
+    Ok(BitsProof {
+        t_lo,
+        t_hi,
+        y,
+        y_lo,
+        y_hi,
+        opening,
+    })
+}
+
+/// Verify that the first length values committed in com are bits
+pub fn verify<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    ck: &CommitmentKey<E, D>,
+    com: &E::G1Affine,
+    length: usize,
+    proof: &BitsProof<E>,
+) -> bool {
+    let n = ck.domain.size();
+    if length > n {
+        return false;
+    }
+    let zeta = challenge::<E>(&(*com, length as u64, proof.t_lo, proof.t_hi));
+    let gamma = challenge::<E>(&(zeta, proof.y, proof.y_lo, proof.y_hi));
+
+    // f(zeta)(f(zeta) - 1) = Z_S(zeta) * t(zeta)
+    let zs = vanishing_poly::<E, D>(&ck.domain, length).evaluate(&zeta);
+    let t = proof.y_lo + zeta.pow([n as u64 - 1]) * proof.y_hi;
+    if proof.y * (proof.y - E::ScalarField::one()) != zs * t {
+        return false;
+    }
+
+    // the evaluations are correct
+    let comb = <E::G1 as VariableBaseMSM>::msm(
+        &[*com, proof.t_lo, proof.t_hi],
+        &[E::ScalarField::one(), gamma, gamma.square()],
+    )
+    .unwrap();
+    let value = proof.y + gamma * proof.y_lo + gamma.square() * proof.y_hi;
+    plain_kzg_verify(ck, &comb.into_affine(), zeta, value, &proof.opening)
+}
+
+impl<E: CurveId> BitsProof<E> {
+    /// Encode the proof in the wire format
+    pub fn to_bytes<D: EvaluationDomain<E::ScalarField>>(
+        &self,
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Vec<u8>, LaconicError> {
+        encode::<E, _>(Kind::BitsProof, ck.domain.size(), self)
+    }
+
+    /// Decode a proof produced for the same commitment key
+    pub fn from_bytes<D: EvaluationDomain<E::ScalarField>>(
+        bytes: &[u8],
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Self, LaconicError> {
+        let (domain_size, proof) = decode::<E, _>(Kind::BitsProof, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        Ok(proof)
+    }
+}
+
+/// Z_S = prod_{i<length} (X - w_i)
+fn vanishing_poly<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    domain: &D,
+    length: usize,
+) -> DensePolynomial<E::ScalarField> {
+    let mut z_s = DensePolynomial::from_coefficients_vec(vec![E::ScalarField::one()]);
+    for i in 0..length {
+        z_s = z_s.naive_mul(&DensePolynomial::from_coefficients_vec(vec![
+            -domain.element(i),
+            E::ScalarField::one(),
+        ]));
+    }
+    z_s
+}
+
+/// Fiat-Shamir challenge from the transcript so far
+fn challenge<E: Pairing>(transcript: &impl CanonicalSerialize) -> E::ScalarField {
+    fiat_shamir::<E>(b"laconic-ot bits proof", transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::{test_rng, UniformRand};
+
+    use super::{prove, verify, BitsProof};
+    use crate::error::LaconicError;
+    use crate::kzg_types::CommitmentKey;
+    use crate::kzg_utils::plain_kzg_com;
+
+    type D = Radix2EvaluationDomain<Fr>;
+
+    #[test]
+    fn test_bits_proof() {
+        let rng = &mut test_rng();
+        let ck = CommitmentKey::<Bls12_381, D>::setup(rng, 8).unwrap();
+
+        // five bits followed by random padding
+        let mut evals: Vec<Fr> = [1u64, 0, 0, 1, 1].iter().map(|b| Fr::from(*b)).collect();
+        evals.extend((0..3).map(|_| Fr::rand(rng)));
+        let com = plain_kzg_com(&ck, &evals);
+
+        for length in 0..=5 {
+            let proof = prove(rng, &ck, &com, &evals, length).unwrap();
+            assert!(verify(&ck, &com, length, &proof));
+            let bytes = proof.to_bytes(&ck).unwrap();
+            assert_eq!(BitsProof::from_bytes(&bytes, &ck), Ok(proof));
+        }
+
+        // the proof is for a fixed length and commitment
+        let proof = prove(rng, &ck, &com, &evals, 5).unwrap();
+        assert!(!verify(&ck, &com, 4, &proof));
+        assert!(!verify(&ck, &com, 6, &proof));
+        let other = plain_kzg_com(&ck, &[Fr::from(1u64); 8]);
+        assert!(!verify(&ck, &other, 5, &proof));
+
+        // a commitment to a value of 2 does not pass, the
+        // remainder of the division by Z_S is simply dropped
+        evals[2] = Fr::from(2u64);
+        let com = plain_kzg_com(&ck, &evals);
+        let proof = prove(rng, &ck, &com, &evals, 5).unwrap();
+        assert!(!verify(&ck, &com, 5, &proof));
+        assert!(verify(
+            &ck,
+            &com,
+            2,
+            &prove(rng, &ck, &com, &evals, 2).unwrap()
+        ));
+
+        // at least two random evaluations are needed for zero-knowledge
+        assert_eq!(
+            prove(rng, &ck, &com, &evals, 7).err(),
+            Some(LaconicError::MessageTooLong { length: 7, max: 6 })
+        );
+    }
+}
@@ -0,0 +1,697 @@
+use ark_ec::pairing::Pairing;
+use ark_ec::{CurveGroup, VariableBaseMSM};
+use ark_ff::{Field, PrimeField};
+use ark_poly::univariate::DensePolynomial;
+use ark_poly::{DenseUVPolynomial, EvaluationDomain, Polynomial};
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{One, UniformRand, Zero};
+use std::collections::BTreeMap;
+use std::ops::Mul;
+
+use crate::error::LaconicError;
+use crate::kzg_types::{CommitmentKey, Opening};
+use crate::kzg_utils::{
+    divide, fiat_shamir, kzg_com_coeffs, plain_kzg_com, plain_kzg_verify, split_quotient,
+};
+use crate::wire::{check_domain_size, decode, encode, CurveId, Kind};
+
+// This module implements a PLONK-style proof for statements about the
+// values committed in a KZG commitment to f, given by its evaluations
+// over the domain. A statement is a list of gates, each of which is a
+// polynomial equation of degree at most two in the values of f and of
+// auxiliary columns a_j of the prover, at a row i and the next row i+1.
+// Gates at the same row are assigned to different layers, and all gates
+// hold iff the polynomial
+//   P = sum_layers lambda^layer sum_monomials q(X) prod_wires w(X)
+// vanishes on the domain, where the selector q(X) interpolates the
+// coefficients of the monomial over the rows and lambda is random.
+// The prover commits to the auxiliary columns and the quotient
+// t = P / Z_H, split as in kzg_bits, and opens all of them at a random
+// point zeta and the columns also at omega * zeta. There are no copy
+// constraints, the auxiliary columns are tied to f by the gates only.
+// The proof reveals f and the a_j at two points, so all columns need at
+// least three random rows that are not referenced by any gate for the
+// proof to be zero-knowledge
+
+/// minimal number of random rows per column for zero-knowledge.
+/// This holds for a single proof: the rows of the auxiliary columns are
+/// sampled anew for every proof, but every proof for the same commitment
+/// reveals f at REVEALED_EVALUATIONS more points, which needs as many
+/// more random rows of f, see LaconicOTRecv::prove_constraints
+pub const RANDOM_ROWS: usize = 3;
+
+/// number of evaluations of f outside of the domain revealed by a proof
+pub const REVEALED_EVALUATIONS: usize = 2;
+
+/// The value of a column at the row of the gate or at the next row
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Wire {
+    /// the committed input f
+    Input,
+    InputNext,
+
+    /// the jth auxiliary column of the prover
+    Aux(usize),
+    AuxNext(usize),
+}
+
+/// The equation sum_k coeff_k * prod(wires_k) = 0 at a row
+#[derive(Clone, Debug, PartialEq, Eq)]
+struct Gate<F: PrimeField> {
+    row: usize,
+    layer: usize,
+    terms: Vec<(F, Vec<Wire>)>,
+}
+
+/// how the prover computes an auxiliary column from the input
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum AuxColumn {
+    /// e_k = 1 iff the first k bits of the input at start..start+len,
+    /// read in big-endian order, are the first k bits of bound
+    Prefix {
+        start: usize,
+        len: usize,
+        bound: u64,
+    },
+}
+
+/// Statement about the committed values. The gates are public and
+/// have to be agreed on by the prover and the verifier
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintSystem<F: PrimeField> {
+    gates: Vec<Gate<F>>,
+    aux: Vec<AuxColumn>,
+}
+
+/// Proof that the committed values satisfy a constraint system
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct ConstraintProof<E: Pairing> {
+    /// commitments to the auxiliary columns
+    aux: Vec<E::G1Affine>,
+
+    /// hiding commitments to the parts of the quotient
+    t_lo: E::G1Affine,
+    t_hi: E::G1Affine,
+
+    /// evaluations of f, the a_j, t_lo and t_hi at zeta
+    evals: Vec<E::ScalarField>,
+
+    /// evaluations of f and the a_j at omega * zeta
+    evals_next: Vec<E::ScalarField>,
+
+    /// openings of the random combinations at zeta and omega * zeta
+    opening: Opening<E>,
+    opening_next: Opening<E>,
+}
+
+impl<F: PrimeField> Default for ConstraintSystem<F> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F: PrimeField> ConstraintSystem<F> {
+    pub fn new() -> Self {
+        Self {
+            gates: Vec::new(),
+            aux: Vec::new(),
+        }
+    }
+
+    /// Add the gate sum_k coeff_k * prod(wires_k) = 0 at the given row.
+    /// Panics if a term has more than two wires
+    /// or refers to an auxiliary column that does not exist
+    pub fn gate(&mut self, row: usize, terms: Vec<(F, Vec<Wire>)>) -> &mut Self {
+        for (_, wires) in terms.iter() {
+            assert!(wires.len() <= 2, "gates have degree at most two");
+            for w in wires {
+                if let Wire::Aux(j) | Wire::AuxNext(j) = w {
+                    assert!(*j < self.aux.len(), "unknown auxiliary column {}", j);
+                }
+            }
+        }
+        let layer = self.gates.iter().filter(|g| g.row == row).count();
+        self.gates.push(Gate { row, layer, terms });
+        self
+    }
+
+    /// The inputs at start..start+len are bits
+    pub fn bits(&mut self, start: usize, len: usize) -> &mut Self {
+        for row in start..start + len {
+            // b^2 - b = 0
+            self.gate(
+                row,
+                vec![
+                    (F::one(), vec![Wire::Input, Wire::Input]),
+                    (-F::one(), vec![Wire::Input]),
+                ],
+            );
+        }
+        self
+    }
+
+    /// The input at row is the given bit
+    pub fn fixed(&mut self, row: usize, bit: bool) -> &mut Self {
+        self.gate(
+            row,
+            vec![(F::one(), vec![Wire::Input]), (-F::from(bit), vec![])],
+        )
+    }
+
+    /// The bits at start..start+len are ones followed by zeros,
+    /// e.g. 1100. Assumes that the inputs are bits
+    pub fn prefix_ones(&mut self, start: usize, len: usize) -> &mut Self {
+        for row in start..(start + len).saturating_sub(1) {
+            // b_{i+1} (1 - b_i) = 0
+            self.gate(
+                row,
+                vec![
+                    (F::one(), vec![Wire::InputNext]),
+                    (-F::one(), vec![Wire::Input, Wire::InputNext]),
+                ],
+            );
+        }
+        self
+    }
+
+    /// The bits at start..start+len are zeros followed by
+    /// at least one one, e.g. 0011. Assumes that the inputs are bits
+    pub fn suffix_ones(&mut self, start: usize, len: usize) -> &mut Self {
+        assert!(len > 0, "the suffix of ones is not empty");
+        for row in start..start + len - 1 {
+            // b_i (1 - b_{i+1}) = 0
+            self.gate(
+                row,
+                vec![
+                    (F::one(), vec![Wire::Input]),
+                    (-F::one(), vec![Wire::Input, Wire::InputNext]),
+                ],
+            );
+        }
+        self.fixed(start + len - 1, true)
+    }
+
+    /// The bits at start..start+len, read as a big-endian number,
+    /// are at most bound. Assumes that the inputs are bits.
+    /// Panics if len is not in 1..=64 or bound has more than len bits
+    pub fn at_most(&mut self, start: usize, len: usize, bound: u64) -> &mut Self {
+        self.compare(start, len, bound, true)
+    }
+
+    /// The bits at start..start+len, read as a big-endian number,
+    /// are at least bound. Assumes that the inputs are bits.
+    /// Panics if len is not in 1..=64 or bound has more than len bits
+    pub fn at_least(&mut self, start: usize, len: usize, bound: u64) -> &mut Self {
+        self.compare(start, len, bound, false)
+    }
+
+    /// the number of auxiliary columns of the prover
+    pub fn num_aux(&self) -> usize {
+        self.aux.len()
+    }
+
+    /// Compare the input with bound using an auxiliary column e,
+    /// where e_k = 1 iff the first k bits of the input and bound agree.
+    /// For at_most, whenever the bit of bound is zero and e_k = 1,
+    /// the input bit must be zero. For at_least, whenever the bit
+    /// of bound is one and e_k = 1, the input bit must be one
+    fn compare(&mut self, start: usize, len: usize, bound: u64, at_most: bool) -> &mut Self {
+        assert!((1..=64).contains(&len), "comparisons are for 1 to 64 bits");
+        assert!(
+            len == 64 || bound >> len == 0,
+            "bound {} has more than {} bits",
+            bound,
+            len
+        );
+        let j = self.aux.len();
+        self.aux.push(AuxColumn::Prefix { start, len, bound });
+        let one = F::one();
+
+        // e_0 = 1
+        self.gate(start, vec![(one, vec![Wire::Aux(j)]), (-one, vec![])]);
+        for k in 0..len {
+            let row = start + k;
+            let u = (bound >> (len - 1 - k)) & 1 == 1;
+            let e = Wire::Aux(j);
+            let b = Wire::Input;
+            if u == at_most {
+                // the bits may agree or differ: e_{k+1} = e_k b_k
+                // for a one in bound, and e_{k+1} = e_k (1 - b_k) for a zero
+                let mut terms = vec![(one, vec![Wire::AuxNext(j)])];
+                if u {
+                    terms.push((-one, vec![e, b]));
+                } else {
+                    terms.extend([(-one, vec![e]), (one, vec![e, b])]);
+                }
+                self.gate(row, terms);
+            } else {
+                // the bits must agree while e_k = 1,
+                // that is e_k b_k = 0 for at_most and e_k (1 - b_k) = 0
+                // for at_least. Then e_{k+1} = e_k
+                let mut terms = vec![(one, vec![e, b])];
+                if !at_most {
+                    terms.insert(0, (-one, vec![e]));
+                }
+                self.gate(row, terms);
+                self.gate(row, vec![(one, vec![Wire::AuxNext(j)]), (-one, vec![e])]);
+            }
+        }
+        self
+    }
+
+    /// the largest row of the input referenced by a gate
+    pub fn max_input_row(&self) -> Option<usize> {
+        self.gates
+            .iter()
+            .flat_map(|g| {
+                g.terms.iter().flat_map(move |(_, wires)| {
+                    wires.iter().filter_map(move |w| match w {
+                        Wire::Input => Some(g.row),
+                        Wire::InputNext => Some(g.row + 1),
+                        _ => None,
+                    })
+                })
+            })
+            .max()
+    }
+
+    /// the values of the auxiliary columns at their rows
+    fn aux_witness(&self, input: &[F]) -> Vec<Vec<(usize, F)>> {
+        self.aux
+            .iter()
+            .map(|aux| match aux {
+                AuxColumn::Prefix { start, len, bound } => {
+                    let mut e = F::one();
+                    let mut values = vec![(*start, e)];
+                    for k in 0..*len {
+                        let u = F::from((bound >> (len - 1 - k)) & 1);
+                        if input.get(start + k) != Some(&u) {
+                            e = F::zero();
+                        }
+                        values.push((start + k + 1, e));
+                    }
+                    values
+                }
+            })
+            .collect()
+    }
+
+    /// check all gates, where columns[0] is the input
+    /// and columns[j + 1] is the jth auxiliary column
+    fn check(&self, columns: &[Vec<F>]) -> Result<(), LaconicError> {
+        let n = columns[0].len();
+        for g in self.gates.iter() {
+            if g.row >= n {
+                return Err(LaconicError::IndexOutOfRange {
+                    index: g.row,
+                    length: n,
+                });
+            }
+            let value = |w: &Wire| match w {
+                Wire::Input => columns[0][g.row],
+                Wire::InputNext => columns[0][(g.row + 1) % n],
+                Wire::Aux(j) => columns[j + 1][g.row],
+                Wire::AuxNext(j) => columns[j + 1][(g.row + 1) % n],
+            };
+            let sum: F = g
+                .terms
+                .iter()
+                .map(|(c, wires)| wires.iter().map(value).fold(*c, |acc, v| acc * v))
+                .sum();
+            if !sum.is_zero() {
+                return Err(LaconicError::ConstraintNotSatisfied { row: g.row });
+            }
+        }
+        Ok(())
+    }
+
+    /// the number of layers, i.e., the largest number of gates at a row
+    fn num_layers(&self) -> usize {
+        self.gates.iter().map(|g| g.layer + 1).max().unwrap_or(0)
+    }
+
+    /// encoding of the gates for the Fiat-Shamir transcript
+    fn to_transcript(&self) -> Vec<u8> {
+        let mut bytes = Vec::new();
+        bytes.extend_from_slice(&(self.aux.len() as u64).to_le_bytes());
+        bytes.extend_from_slice(&(self.gates.len() as u64).to_le_bytes());
+        for g in self.gates.iter() {
+            bytes.extend_from_slice(&(g.row as u64).to_le_bytes());
+            bytes.extend_from_slice(&(g.layer as u64).to_le_bytes());
+            bytes.extend_from_slice(&(g.terms.len() as u64).to_le_bytes());
+            for (c, wires) in g.terms.iter() {
+                c.serialize_compressed(&mut bytes).unwrap();
+                bytes.push(wires.len() as u8);
+                for w in wires {
+                    let code = match w {
+                        Wire::Input => 0,
+                        Wire::InputNext => 1,
+                        Wire::Aux(j) => 2 * *j as u64 + 2,
+                        Wire::AuxNext(j) => 2 * *j as u64 + 3,
+                    };
+                    bytes.extend_from_slice(&code.to_le_bytes());
+                }
+            }
+        }
+        bytes
+    }
+}
+
+/// Prove that the values committed in com satisfy cs.
+/// evals are the evaluations of the polynomial committed in com, which
+/// must be random on at least RANDOM_ROWS rows not referenced by cs
+pub fn prove<E: Pairing, D: EvaluationDomain<E::ScalarField>, R: rand::Rng>(
+    rng: &mut R,
+    ck: &CommitmentKey<E, D>,
+    com: &E::G1Affine,
+    evals: &[E::ScalarField],
+    cs: &ConstraintSystem<E::ScalarField>,
+) -> Result<ConstraintProof<E>, LaconicError> {
+    let n = ck.domain.size();
+
+    // auxiliary columns, random outside of the rows they are used at
+    let mut columns = vec![evals.to_vec()];
+    for values in cs.aux_witness(evals) {
+        if values.len() + RANDOM_ROWS > n {
+            return Err(LaconicError::MessageTooLong {
+                length: values.len(),
+                max: n - RANDOM_ROWS,
+            });
+        }
+        let mut column: Vec<_> = (0..n).map(|_| E::ScalarField::rand(rng)).collect();
+        for (row, v) in values {
+            if row >= n {
+                return Err(LaconicError::IndexOutOfRange {
+                    index: row,
+                    length: n,
+                });
+            }
+            column[row] = v;
+        }
+        columns.push(column);
+    }
+    cs.check(&columns)?;
+    let aux: Vec<_> = columns[1..].iter().map(|c| plain_kzg_com(ck, c)).collect();
+    let lambda = challenge::<E>(&(cs.to_transcript(), *com, aux.clone()));
+
+    // coefficient form of the columns, and of the columns shifted by omega
+    let omega = ck.domain.group_gen();
+    let polys: Vec<_> = columns
+        .iter()
+        .map(|c| DensePolynomial::from_coefficients_vec(ck.domain.ifft(c)))
+        .collect();
+    let shifted: Vec<Vec<_>> = polys
+        .iter()
+        .map(|p| {
+            let mut pow = E::ScalarField::one();
+            p.coeffs
+                .iter()
+                .map(|c| {
+                    let c = *c * pow;
+                    pow *= omega;
+                    c
+                })
+                .collect()
+        })
+        .collect();
+
+    // P has degree at most 3n - 3, so we compute
+    // it over a domain of four times the size
+    let domain4 = D::new(4 * n).unwrap();
+    let wire_evals: Vec<[Vec<_>; 2]> = polys
+        .iter()
+        .zip(shifted.iter())
+        .map(|(p, s)| [domain4.fft(&p.coeffs), domain4.fft(s)])
+        .collect();
+    let wire = |w: &Wire| match w {
+        Wire::Input => &wire_evals[0][0],
+        Wire::InputNext => &wire_evals[0][1],
+        Wire::Aux(j) => &wire_evals[j + 1][0],
+        Wire::AuxNext(j) => &wire_evals[j + 1][1],
+    };
+
+    // one selector per layer and monomial
+    let mut selectors: BTreeMap<(usize, Vec<Wire>), Vec<E::ScalarField>> = BTreeMap::new();
+    for g in cs.gates.iter() {
+        for (c, wires) in g.terms.iter() {
+            let mut wires = wires.clone();
+            wires.sort();
+            let sel = selectors
+                .entry((g.layer, wires))
+                .or_insert_with(|| vec![E::ScalarField::zero(); n]);
+            sel[g.row] += c;
+        }
+    }
+    let lambdas: Vec<_> = (0..cs.num_layers())
+        .map(|l| lambda.pow([l as u64]))
+        .collect();
+    let mut p = vec![E::ScalarField::zero(); domain4.size()];
+    for ((layer, wires), sel) in selectors {
+        let mut q = domain4.fft(&ck.domain.ifft(&sel));
+        for w in wires.iter() {
+            q.iter_mut().zip(wire(w)).for_each(|(q, v)| *q *= v);
+        }
+        p.iter_mut()
+            .zip(q)
+            .for_each(|(p, q)| *p += lambdas[layer] * q);
+    }
+    let p = DensePolynomial::from_coefficients_vec(domain4.ifft(&p));
+    let (t, _) = p.divide_by_vanishing_poly(ck.domain).unwrap();
+    let (lo, hi) = split_quotient::<E, R>(rng, &t, n);
+
+    // hide the commitments with constant masking polynomials
+    let r_lo = E::ScalarField::rand(rng);
+    let r_hi = E::ScalarField::rand(rng);
+    let h1 = ck.h1()?;
+    let t_lo = (kzg_com_coeffs::<E>(&ck.u, &lo) + h1.mul(r_lo)).into_affine();
+    let t_hi = (kzg_com_coeffs::<E>(&ck.u, &hi) + h1.mul(r_hi)).into_affine();
+
+    let zeta = challenge::<E>(&(lambda, t_lo, t_hi));
+    let evals: Vec<_> = polys
+        .iter()
+        .chain([&lo, &hi])
+        .map(|p| p.evaluate(&zeta))
+        .collect();
+    let evals_next: Vec<_> = polys.iter().map(|p| p.evaluate(&(omega * zeta))).collect();
+    let gamma = challenge::<E>(&(zeta, evals.clone(), evals_next.clone()));
+
+    // open the random combinations, the masking parts are constant
+    let m = polys.len();
+    let gammas: Vec<_> = (0..m + 2).map(|k| gamma.pow([k as u64])).collect();
+    let combine = |ps: &[&DensePolynomial<E::ScalarField>]| {
+        ps.iter()
+            .zip(gammas.iter())
+            .fold(DensePolynomial::zero(), |acc, (p, g)| &acc + &(*p * *g))
+    };
+    let all: Vec<_> = polys.iter().chain([&lo, &hi]).collect();
+    let open_at = |p: &DensePolynomial<E::ScalarField>, z: E::ScalarField| {
+        let x_minus_z = DensePolynomial::from_coefficients_vec(vec![-z, E::ScalarField::one()]);
+        kzg_com_coeffs::<E>(&ck.u, &divide::<E>(p, &x_minus_z)).into_affine()
+    };
+    let opening = Opening {
+        v: open_at(&combine(&all), zeta),
+        hat_y: gammas[m] * r_lo + gammas[m + 1] * r_hi,
+    };
+    let opening_next = Opening {
+        v: open_at(&combine(&all[0..m]), omega * zeta),
+        hat_y: E::ScalarField::zero(),
+    };
+
+    Ok(ConstraintProof {
+        aux,
+        t_lo,
+        t_hi,
+        evals,
+        evals_next,
+        opening,
+        opening_next,
+    })
+}
+
+/// Verify that the values committed in com satisfy cs
+pub fn verify<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    ck: &CommitmentKey<E, D>,
+    com: &E::G1Affine,
+    cs: &ConstraintSystem<E::ScalarField>,
+    proof: &ConstraintProof<E>,
+) -> bool {
+    let n = ck.domain.size();
+    let m = cs.num_aux() + 1;
+    if proof.aux.len() != m - 1
+        || proof.evals.len() != m + 2
+        || proof.evals_next.len() != m
+        || cs.gates.iter().any(|g| g.row >= n)
+    {
+        return false;
+    }
+    let lambda = challenge::<E>(&(cs.to_transcript(), *com, proof.aux.clone()));
+    let zeta = challenge::<E>(&(lambda, proof.t_lo, proof.t_hi));
+    let gamma = challenge::<E>(&(zeta, proof.evals.clone(), proof.evals_next.clone()));
+
+    // P(zeta) = sum_gates lambda^layer L_row(zeta) sum_terms coeff prod_wires
+    let lagranges = ck.domain.evaluate_all_lagrange_coefficients(zeta);
+    let value = |w: &Wire| match w {
+        Wire::Input => proof.evals[0],
+        Wire::InputNext => proof.evals_next[0],
+        Wire::Aux(j) => proof.evals[j + 1],
+        Wire::AuxNext(j) => proof.evals_next[j + 1],
+    };
+    let p: E::ScalarField = cs
+        .gates
+        .iter()
+        .map(|g| {
+            let sum: E::ScalarField = g
+                .terms
+                .iter()
+                .map(|(c, wires)| wires.iter().map(value).fold(*c, |acc, v| acc * v))
+                .sum();
+            lambda.pow([g.layer as u64]) * lagranges[g.row] * sum
+        })
+        .sum();
+
+    // P(zeta) = Z_H(zeta) * t(zeta)
+    let t = proof.evals[m] + zeta.pow([n as u64 - 1]) * proof.evals[m + 1];
+    if p != ck.domain.evaluate_vanishing_polynomial(zeta) * t {
+        return false;
+    }
+
+    // the evaluations are correct
+    let gammas: Vec<_> = (0..m + 2).map(|k| gamma.pow([k as u64])).collect();
+    let mut coms = vec![*com];
+    coms.extend_from_slice(&proof.aux);
+    coms.extend([proof.t_lo, proof.t_hi]);
+    let combine = |coms: &[E::G1Affine], evals: &[E::ScalarField]| {
+        let c = <E::G1 as VariableBaseMSM>::msm(coms, &gammas[0..coms.len()]).unwrap();
+        let y: E::ScalarField = evals.iter().zip(gammas.iter()).map(|(y, g)| *y * g).sum();
+        (c.into_affine(), y)
+    };
+    let (c, y) = combine(&coms, &proof.evals);
+    let (c_next, y_next) = combine(&coms[0..m], &proof.evals_next);
+    let omega = ck.domain.group_gen();
+    plain_kzg_verify(ck, &c, zeta, y, &proof.opening)
+        && plain_kzg_verify(ck, &c_next, omega * zeta, y_next, &proof.opening_next)
+}
+
+impl<E: CurveId> ConstraintProof<E> {
+    /// Encode the proof in the wire format
+    pub fn to_bytes<D: EvaluationDomain<E::ScalarField>>(
+        &self,
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Vec<u8>, LaconicError> {
+        encode::<E, _>(Kind::ConstraintProof, ck.domain.size(), self)
+    }
+
+    /// Decode a proof produced for the same commitment key
+    pub fn from_bytes<D: EvaluationDomain<E::ScalarField>>(
+        bytes: &[u8],
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Self, LaconicError> {
+        let (domain_size, proof) = decode::<E, _>(Kind::ConstraintProof, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        Ok(proof)
+    }
+}
+
+/// Fiat-Shamir challenge from the transcript so far
+fn challenge<E: Pairing>(transcript: &impl CanonicalSerialize) -> E::ScalarField {
+    fiat_shamir::<E>(b"laconic-ot constraint proof", transcript)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::{test_rng, UniformRand};
+
+    use super::{prove, verify, ConstraintProof, ConstraintSystem, Wire};
+    use crate::error::LaconicError;
+    use crate::kzg_types::CommitmentKey;
+    use crate::kzg_utils::plain_kzg_com;
+
+    type D = Radix2EvaluationDomain<Fr>;
+
+    /// big-endian bits of value, followed by random padding
+    fn evals(bits: &[u64], n: usize) -> Vec<Fr> {
+        let rng = &mut test_rng();
+        let mut evals: Vec<Fr> = bits.iter().map(|b| Fr::from(*b)).collect();
+        evals.resize_with(n, || Fr::rand(rng));
+        evals
+    }
+
+    #[test]
+    fn test_constraints() {
+        let rng = &mut test_rng();
+        let ck = CommitmentKey::<Bls12_381, D>::setup(rng, 16).unwrap();
+
+        // unary 1100, unary 0011 and the salary 0b101101 = 45
+        let bits = [1, 1, 0, 0, 0, 0, 1, 1, 1, 0, 1, 1, 0, 1];
+        let evals = evals(&bits, 16);
+        let com = plain_kzg_com(&ck, &evals);
+
+        let mut cs = ConstraintSystem::new();
+        cs.bits(0, 14)
+            .prefix_ones(0, 4)
+            .suffix_ones(4, 4)
+            .at_least(8, 6, 40)
+            .at_most(8, 6, 45)
+            .fixed(1, true);
+        assert_eq!(cs.num_aux(), 2);
+
+        let proof = prove(rng, &ck, &com, &evals, &cs).unwrap();
+        assert!(verify(&ck, &com, &cs, &proof));
+        let bytes = proof.to_bytes(&ck).unwrap();
+        assert_eq!(ConstraintProof::from_bytes(&bytes, &ck), Ok(proof.clone()));
+
+        // the proof is for a fixed statement and commitment
+        let mut other = cs.clone();
+        other.fixed(2, false);
+        assert!(!verify(&ck, &com, &other, &proof));
+        let mut other = evals.clone();
+        other[15] += Fr::from(1u64);
+        let other = plain_kzg_com(&ck, &other);
+        assert!(!verify(&ck, &other, &cs, &proof));
+
+        // the prover can not prove false statements
+        for (cs, row) in [
+            (ConstraintSystem::new().prefix_ones(4, 4).clone(), 5),
+            (ConstraintSystem::new().suffix_ones(0, 4).clone(), 1),
+            (ConstraintSystem::new().at_least(8, 6, 46).clone(), 12),
+            (ConstraintSystem::new().at_most(8, 6, 44).clone(), 13),
+            (ConstraintSystem::new().at_most(8, 6, 31).clone(), 8),
+        ] {
+            assert_eq!(
+                prove(rng, &ck, &com, &evals, &cs).err(),
+                Some(LaconicError::ConstraintNotSatisfied { row })
+            );
+        }
+    }
+
+    #[test]
+    fn test_constraints_soundness() {
+        let rng = &mut test_rng();
+        let ck = CommitmentKey::<Bls12_381, D>::setup(rng, 8).unwrap();
+
+        // custom gates: the first two values are equal, the third is zero
+        let mut cs = ConstraintSystem::new();
+        cs.gate(
+            0,
+            vec![
+                (Fr::from(1u64), vec![Wire::Input]),
+                (-Fr::from(1u64), vec![Wire::InputNext]),
+            ],
+        )
+        .gate(2, vec![(Fr::from(1u64), vec![Wire::Input])]);
+        let evals = evals(&[3, 3, 0], 8);
+        let com = plain_kzg_com(&ck, &evals);
+        let proof = prove(rng, &ck, &com, &evals, &cs).unwrap();
+        assert!(verify(&ck, &com, &cs, &proof));
+
+        // tampering with the claimed evaluations is detected
+        let mut bad = proof.clone();
+        bad.evals[0] += Fr::from(1u64);
+        assert!(!verify(&ck, &com, &cs, &bad));
+        let mut bad = proof.clone();
+        bad.evals_next.pop();
+        assert!(!verify(&ck, &com, &cs, &bad));
+    }
+}
@@ -3,8 +3,6 @@ use ark_poly::EvaluationDomain;
 use ark_std::Zero;
 use std::ops::Mul;
 
-use crate::kzg_types::{CommitmentKey, State};
-
 // this module allows to compute all openings in a
 // fast amortized way following the FK technique:
 // https://eprint.iacr.org/2023/033.pdf
@@ -43,7 +41,7 @@ pub fn all_openings_single<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
     evals: &[E::ScalarField],
 ) -> Vec<E::G1> {
     // compute the base polynomial h
-    let coeffs = domain.ifft(&evals);
+    let coeffs = domain.ifft(evals);
     let mut h = base_poly::<E, D>(y, domain, &coeffs);
 
     // evaluate h in the exponent using FFT
@@ -52,6 +50,22 @@ pub fn all_openings_single<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
     h
 }
 
+/// FK technique to compute openings in a *hiding* way
+/// evals contains 2 * domain.size() many evaluations, first those
+/// of the polynomial, then those of the masking polynomial.
+/// hat_y is computed as y, but from the hiding powers hat_u
+pub fn all_openings<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    y: &[E::G1Affine],
+    hat_y: &[E::G1Affine],
+    domain: &D,
+    evals: &[E::ScalarField],
+) -> Vec<E::G1> {
+    let n = domain.size();
+    let vs = all_openings_single::<E, D>(y, domain, &evals[0..n]);
+    let hat_vs = all_openings_single::<E, D>(hat_y, domain, &evals[n..2 * n]);
+    vs.iter().zip(hat_vs).map(|(v, hat_v)| *v + hat_v).collect()
+}
+
 /// compute the polynomial h (in exponent) from the paper (see Proposition 1)
 /// The polynomial f is given by domain.size() many coefficients, and we have
 /// powers[i] = g1^{alpha^i}
@@ -81,9 +95,7 @@ fn base_poly<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
         hat_c.push(E::ScalarField::zero());
     }
     hat_c.push(coeffs[d]);
-    for i in 0..d {
-        hat_c.push(coeffs[i]);
-    }
+    hat_c.extend_from_slice(&coeffs[0..d]);
 
     // let v = domain2.fft(&hat_c);
     domain2.fft_in_place(&mut hat_c);
@@ -99,8 +111,7 @@ fn base_poly<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
     //let hat_h = domain2.ifft(&u);
     domain2.ifft_in_place(&mut u);
     let hat_h = u;
-    let h = hat_h[0..d].to_vec();
-    h
+    hat_h[0..d].to_vec()
 }
 
 #[cfg(test)]
@@ -117,7 +128,7 @@ mod tests {
     use ark_std::One;
     use ark_std::UniformRand;
 
-    use crate::kzg_types::VcKZG;
+    use crate::kzg_types::{State, VcKZG};
 
     use super::{all_openings_single, base_poly};
 
@@ -178,10 +189,10 @@ mod tests {
             let evals = ck.domain.fft(&f.coeffs);
             // precompute the openings naively using long division (very slow)
             let mut naive: Vec<<Bls12_381 as Pairing>::G1Affine> = Vec::new();
-            for i in 0..ck.domain.size() {
+            for (i, fi) in evals.iter().enumerate() {
                 // witness poly using long division
                 let z = ck.domain.element(i);
-                let fshift = &f - &DensePolynomial::from_coefficients_vec(vec![evals[i]]);
+                let fshift = &f - &DensePolynomial::from_coefficients_vec(vec![*fi]);
                 let div = DensePolynomial::from_coefficients_vec(vec![-z, F::one()]);
                 let witness_poly = &fshift / &div;
                 // commit to witness poly at alpha
@@ -214,13 +225,18 @@ mod tests {
             let ck = VcKZG::<Bls12_381, D>::setup(&mut rng, degree - 1).unwrap();
 
             // commit to something
-            let m = (0..degree - 1).map(|_| F::rand(&mut rng)).collect();
-            let (_com, mut st) = VcKZG::<Bls12_381, D>::commit(&mut rng, &ck, &m);
+            let m: Vec<F> = (0..degree - 1).map(|_| F::rand(&mut rng)).collect();
+            let (_com, mut st) = VcKZG::<Bls12_381, D>::commit(&mut rng, &ck, &m).unwrap();
+            VcKZG::<Bls12_381, D>::precompute_all_openings(&ck, &mut st);
 
             // compute all the openings freshly
+            let st_fresh = State {
+                evals: st.evals.clone(),
+                precomputed_v: None,
+            };
             let mut openings = Vec::new();
             for i in 0..ck.message_length {
-                let op = VcKZG::<Bls12_381, D>::open(&ck, &st, i as u32).unwrap();
+                let op = VcKZG::<Bls12_381, D>::open(&ck, &st_fresh, i as u32).unwrap();
                 openings.push(op.v);
             }
 
@@ -1,13 +1,17 @@
 use ark_ec::pairing::Pairing;
-use ark_ec::CurveGroup;
+use ark_ec::{AffineRepr, CurveGroup, VariableBaseMSM};
 use ark_poly::EvaluationDomain;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
 use ark_std::UniformRand;
 use ark_std::Zero;
 use std::marker::PhantomData;
-use std::ops::Mul;
+use std::ops::{Deref, Mul};
+use std::sync::Arc;
 
+use crate::error::LaconicError;
 use crate::kzg_fk_open::precompute_y;
+use crate::kzg_utils::check_powers;
+use crate::wire::{check_domain_size, decode, encode, CurveId, Kind};
 
 /// Simulation-Extractable vector commitment based on KZG
 pub struct VcKZG<E: Pairing, D: EvaluationDomain<E::ScalarField>> {
@@ -38,13 +42,15 @@ pub struct CommitmentKey<E: Pairing, D: EvaluationDomain<E::ScalarField>> {
     /// same as u, but for the hiding part
     /// hat_u[i] = h1^{alpha^i}
     /// i should range from 0 to deg
+    /// empty for keys without a hiding part, see from_powers_of_tau
     pub hat_u: Vec<E::G1Affine>,
 
     /// lagrange version of u and hat_u
     /// Let l_i be the ith lagrange poly. Then:
     /// lag[i] = g1^{l_i(alpha)}
     /// lag[deg+i] = h1^{l_i(alpha)}
-    /// for i in 0..deg
+    /// for i in 0..deg, where the second half is
+    /// missing for keys without a hiding part
     pub lagranges: Vec<E::G1Affine>,
 
     /// generator of G2
@@ -66,10 +72,36 @@ pub struct CommitmentKey<E: Pairing, D: EvaluationDomain<E::ScalarField>> {
     pub y: Vec<E::G1Affine>,
 }
 
-#[derive(CanonicalSerialize)]
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
 pub struct Opening<E: Pairing> {
     /// commitment to witness polynomial g1^{psi(alpha)}
+    /// and for hiding commitments also h1^{hat_psi(alpha)}
+    pub v: E::G1Affine,
+
+    /// evaluation of the masking polynomial,
+    /// zero for non-hiding commitments
+    pub hat_y: E::ScalarField,
+}
+
+/// Opening of a subset of the committed positions with a constant-size
+/// proof. Let S be the subset, Z_S its vanishing polynomial and I_S the
+/// polynomial interpolating the opened values over S. Following
+/// https://eprint.iacr.org/2020/081.pdf, w commits to (f - I_S) / Z_S,
+/// and v is a standard KZG opening at a Fiat-Shamir challenge z of the
+/// linearized polynomial f - I_S(z) - Z_S(z) * (f - I_S) / Z_S,
+/// so the verifier only needs g1, h1, g2 and r
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct MultiOpening<E: Pairing> {
+    /// commitment to the quotient polynomials
+    /// g1^{q(alpha)} * h1^{hat_q(alpha)}
+    pub w: E::G1Affine,
+
+    /// opening of the linearized polynomial at z
     pub v: E::G1Affine,
+
+    /// evaluation of the linearized masking polynomial at z,
+    /// zero for non-hiding commitments
+    pub hat_y: E::ScalarField,
 }
 
 #[derive(CanonicalSerialize)]
@@ -88,28 +120,66 @@ pub struct State<E: Pairing> {
     pub precomputed_v: Option<Vec<E::G1>>,
 }
 
+/// A commitment key that is either borrowed or shared.
+/// A shared key lets the OT parties own their key without
+/// a lifetime, while many parties still use the same copy
+#[derive(Clone, Debug)]
+pub enum CommitmentKeyRef<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> {
+    Borrowed(&'a CommitmentKey<E, D>),
+    Shared(Arc<CommitmentKey<E, D>>),
+}
+
+impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> Deref for CommitmentKeyRef<'_, E, D> {
+    type Target = CommitmentKey<E, D>;
+
+    fn deref(&self) -> &Self::Target {
+        match self {
+            CommitmentKeyRef::Borrowed(ck) => ck,
+            CommitmentKeyRef::Shared(ck) => ck,
+        }
+    }
+}
+
+impl<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> From<&'a CommitmentKey<E, D>>
+    for CommitmentKeyRef<'a, E, D>
+{
+    fn from(ck: &'a CommitmentKey<E, D>) -> Self {
+        CommitmentKeyRef::Borrowed(ck)
+    }
+}
+
+impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> From<Arc<CommitmentKey<E, D>>>
+    for CommitmentKeyRef<'_, E, D>
+{
+    fn from(ck: Arc<CommitmentKey<E, D>>) -> Self {
+        CommitmentKeyRef::Shared(ck)
+    }
+}
+
 impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> CommitmentKey<E, D> {
     pub fn setup<R: rand::Rng>(
         rng: &mut R,
         message_length: usize,
-    ) -> Result<CommitmentKey<E, D>, ()> {
+    ) -> Result<CommitmentKey<E, D>, LaconicError> {
         if message_length < 1 {
-            return Err(());
+            return Err(LaconicError::DomainTooSmall { message_length });
         }
 
         // generate an evaluation domain
         // should support polynomials to degree >= message_length + 1
-        let domain = D::new(message_length);
-        if domain.is_none() {
-            return Err(());
-        }
-        let domain = domain.unwrap();
+        // the FK technique needs a domain of size at least 2
+        let domain =
+            D::new(message_length.max(2)).ok_or(LaconicError::DomainTooSmall { message_length })?;
 
         // sample generators g1 and g2
-        let g1 = E::G1::rand(rng);
-        let g2 = E::G2::rand(rng);
-        if g1.is_zero() || g2.is_zero() {
-            return Err(());
+        // resample in the (negligible) case that we hit the identity
+        let mut g1 = E::G1::rand(rng);
+        while g1.is_zero() {
+            g1 = E::G1::rand(rng);
+        }
+        let mut g2 = E::G2::rand(rng);
+        while g2.is_zero() {
+            g2 = E::G2::rand(rng);
         }
 
         // sample hiding generator h
@@ -137,11 +207,11 @@ impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> CommitmentKey<E, D> {
         // compute exponentiated lagrange coefficients
         // Note: If a standard powers-of-tau setup is used,
         // this can be publicly computed from u and hat_u
+        // (see from_powers_of_tau)
         let lf = domain.evaluate_all_lagrange_coefficients(alpha);
-        let mut lagranges = Vec::with_capacity(2 * deg);
-        for i in 0..=deg {
-            lagranges.push(u[0].mul(lf[i]).into_affine());
-        }
+        let mut lagranges = Vec::with_capacity(2 * (deg + 1));
+        lagranges.extend(lf.iter().map(|l| u[0].mul(l).into_affine()));
+        lagranges.extend(lf.iter().map(|l| hat_u[0].mul(l).into_affine()));
 
         //compute r = g2^{alpha}
         let r = g2.mul(alpha).into_affine();
@@ -171,4 +241,551 @@ impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> CommitmentKey<E, D> {
             y,
         })
     }
+
+    /// Build a commitment key from a public powers-of-tau transcript, so
+    /// that nobody involved in the protocol has to know the secret alpha.
+    /// Expects powers[i] = g1^{alpha^i} for i in 0..n, where n is the size
+    /// of the evaluation domain, and r = g2^{alpha}. Such a transcript has
+    /// no powers of an independent h1, so the key has no hiding part. It
+    /// can be used for laconic OT, but not for hiding commitments or the
+    /// proofs about them, see from_hiding_powers_of_tau.
+    /// Everything else is derived deterministically from the transcript.
+    /// Note: this does not check that the transcript is well-formed
+    pub fn from_powers_of_tau(
+        message_length: usize,
+        powers: &[E::G1Affine],
+        g2: E::G2Affine,
+        r: E::G2Affine,
+    ) -> Result<CommitmentKey<E, D>, LaconicError> {
+        Self::from_transcript(message_length, powers, None, g2, r)
+    }
+
+    /// As from_powers_of_tau, but with hat_powers[i] = h1^{alpha^i}
+    /// for i in 0..n, which gives a key with a hiding part.
+    /// Nobody may know the discrete logarithm of h1 with respect to g1,
+    /// otherwise openings of hiding commitments are not binding.
+    /// In particular, h1 must not be one of the powers of g1,
+    /// see Ceremony for a transcript with an independent h1
+    pub fn from_hiding_powers_of_tau(
+        message_length: usize,
+        powers: &[E::G1Affine],
+        hat_powers: &[E::G1Affine],
+        g2: E::G2Affine,
+        r: E::G2Affine,
+    ) -> Result<CommitmentKey<E, D>, LaconicError> {
+        Self::from_transcript(message_length, powers, Some(hat_powers), g2, r)
+    }
+
+    fn from_transcript(
+        message_length: usize,
+        powers: &[E::G1Affine],
+        hat_powers: Option<&[E::G1Affine]>,
+        g2: E::G2Affine,
+        r: E::G2Affine,
+    ) -> Result<CommitmentKey<E, D>, LaconicError> {
+        if message_length < 1 {
+            return Err(LaconicError::DomainTooSmall { message_length });
+        }
+        let domain =
+            D::new(message_length.max(2)).ok_or(LaconicError::DomainTooSmall { message_length })?;
+
+        let n = domain.size();
+        let available = powers.len().min(hat_powers.map_or(n, |p| p.len()));
+        if available < n {
+            return Err(LaconicError::NotEnoughPowers {
+                available,
+                required: n,
+            });
+        }
+        let u = powers[0..n].to_vec();
+        let hat_u = match hat_powers {
+            // h1 = g1^{alpha^k} would give away its discrete logarithm
+            Some(hat_powers) if hat_powers[0].is_zero() || powers.contains(&hat_powers[0]) => {
+                return Err(LaconicError::InvalidCommitmentKey("generators"));
+            }
+            Some(hat_powers) => hat_powers[0..n].to_vec(),
+            None => Vec::new(),
+        };
+
+        // compute exponentiated lagrange coefficients
+        // lagranges[i] = g1^{l_i(alpha)} = 1/n * sum_j w^{-ij} u[j],
+        // which is exactly the inverse DFT of u. Same for hat_u
+        let lagranges = lagranges_from_powers::<E, D>(&domain, &u, &hat_u);
+
+        // compute all d[i] = g2^{alpha - zi} = r * g2^{-zi}
+        let mut d = Vec::new();
+        for i in 0..message_length {
+            let z = domain.element(i);
+            d.push((r.into_group() - g2.mul(z)).into_affine());
+        }
+
+        // precompute y for FK algorithm
+        let y = precompute_y::<E, D>(&u, &domain);
+
+        Ok(CommitmentKey {
+            message_length,
+            domain,
+            u,
+            hat_u,
+            lagranges,
+            g2,
+            r,
+            d,
+            y,
+        })
+    }
+
+    /// The generator h1 of the hiding part, needed
+    /// for hiding commitments and the proofs about them
+    pub fn h1(&self) -> Result<E::G1Affine, LaconicError> {
+        self.hat_u.first().copied().ok_or(LaconicError::NotHiding)
+    }
+
+    /// Check that the commitment key is well-formed, i.e., that
+    /// u and hat_u (if any) are powers of alpha with r = g2^{alpha}, and that
+    /// lagranges, d and y are correctly derived from them.
+    /// This recomputes everything and does a pairing check per power,
+    /// see verify_batched for a much cheaper randomized version
+    pub fn verify(&self) -> Result<(), LaconicError> {
+        self.check_shape()?;
+        self.check_hiding_generator()?;
+
+        // e(u[i+1], g2) = e(u[i], r)
+        for powers in [&self.u, &self.hat_u] {
+            for pair in powers.windows(2) {
+                let left = vec![
+                    E::G1Prepared::from(pair[1]),
+                    E::G1Prepared::from(-pair[0].into_group()),
+                ];
+                let right = vec![E::G2Prepared::from(self.g2), E::G2Prepared::from(self.r)];
+                if !E::multi_pairing(left, right).is_zero() {
+                    return Err(LaconicError::InvalidCommitmentKey("powers"));
+                }
+            }
+        }
+
+        // lagranges = iDFT(u) || iDFT(hat_u)
+        if lagranges_from_powers::<E, D>(&self.domain, &self.u, &self.hat_u) != self.lagranges {
+            return Err(LaconicError::InvalidCommitmentKey("lagranges"));
+        }
+
+        // d[i] = r * g2^{-zi}
+        for i in 0..self.message_length {
+            let z = self.domain.element(i);
+            if (self.r.into_group() - self.g2.mul(z)).into_affine() != self.d[i] {
+                return Err(LaconicError::InvalidCommitmentKey("d"));
+            }
+        }
+
+        if precompute_y::<E, D>(&self.u, &self.domain) != self.y {
+            return Err(LaconicError::InvalidCommitmentKey("y"));
+        }
+        Ok(())
+    }
+
+    /// Same checks as verify, but each family of equations is combined
+    /// using random coefficients. This needs a constant number of pairings
+    /// and a few MSMs and scalar FFTs, which is cheap enough for the browser.
+    /// A malformed key passes with probability at most n/|F|
+    pub fn verify_batched<R: rand::Rng>(&self, rng: &mut R) -> Result<(), LaconicError> {
+        self.check_shape()?;
+        self.check_hiding_generator()?;
+        let n = self.domain.size();
+
+        if !check_powers::<E, R>(rng, &self.u, self.g2, self.r)
+            || !check_powers::<E, R>(rng, &self.hat_u, self.g2, self.r)
+        {
+            return Err(LaconicError::InvalidCommitmentKey("powers"));
+        }
+
+        // lagranges = M * u || M * hat_u for the (symmetric) iDFT matrix M.
+        // Hence, <c || c', lagranges> = <M * c, u> + <M * c', hat_u> for random c, c'
+        let c: Vec<E::ScalarField> = (0..self.lagranges.len())
+            .map(|_| E::ScalarField::rand(rng))
+            .collect();
+        let mc: Vec<E::ScalarField> = c.chunks(n).flat_map(|c| self.domain.ifft(c)).collect();
+        let powers: Vec<E::G1Affine> = self.u.iter().chain(&self.hat_u).copied().collect();
+        let lhs = <E::G1 as VariableBaseMSM>::msm(&self.lagranges, &c).unwrap();
+        let rhs = <E::G1 as VariableBaseMSM>::msm(&powers, &mc).unwrap();
+        if lhs != rhs {
+            return Err(LaconicError::InvalidCommitmentKey("lagranges"));
+        }
+
+        // sum_i c_i d[i] = r^{sum_i c_i} * g2^{-sum_i c_i zi}
+        let c: Vec<E::ScalarField> = (0..self.message_length)
+            .map(|_| E::ScalarField::rand(rng))
+            .collect();
+        let mut c_sum = E::ScalarField::zero();
+        let mut cz_sum = E::ScalarField::zero();
+        for (i, ci) in c.iter().enumerate() {
+            c_sum += ci;
+            cz_sum += *ci * self.domain.element(i);
+        }
+        let lhs = <E::G2 as VariableBaseMSM>::msm(&self.d, &c).unwrap();
+        let rhs = self.r.mul(c_sum) - self.g2.mul(cz_sum);
+        if lhs != rhs {
+            return Err(LaconicError::InvalidCommitmentKey("d"));
+        }
+
+        // y = M * hat_s for the (symmetric) DFT matrix M of twice the size,
+        // where hat_s = [u[n-2],...,u[0], n+1 neutral elements].
+        // Hence, <c, y> = <M * c, hat_s> for random c
+        let domain2 = D::new(2 * n).unwrap();
+        let c: Vec<E::ScalarField> = (0..2 * n).map(|_| E::ScalarField::rand(rng)).collect();
+        let mc = domain2.fft(&c);
+        let hat_s: Vec<E::G1Affine> = self.u[0..n - 1].iter().rev().copied().collect();
+        let lhs = <E::G1 as VariableBaseMSM>::msm(&self.y, &c).unwrap();
+        let rhs = <E::G1 as VariableBaseMSM>::msm(&hat_s, &mc[0..n - 1]).unwrap();
+        if lhs != rhs {
+            return Err(LaconicError::InvalidCommitmentKey("y"));
+        }
+        Ok(())
+    }
+
+    /// Encode the key in the compressed wire format
+    pub fn to_bytes(&self) -> Result<Vec<u8>, LaconicError>
+    where
+        E: CurveId,
+    {
+        encode::<E, _>(Kind::CommitmentKey, self.domain.size(), self)
+    }
+
+    /// Decode a key from the wire format. All points are checked to be
+    /// in the subgroup, but the key is not verified, see verify_batched
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, LaconicError>
+    where
+        E: CurveId,
+    {
+        let (domain_size, ck): (usize, Self) = decode::<E, _>(Kind::CommitmentKey, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        ck.check_shape()?;
+        Ok(ck)
+    }
+
+    /// The discrete logarithm of h1 = hat_u[0] has to be unknown,
+    /// otherwise hiding openings are not binding. This rejects
+    /// h1 = g1^{alpha^k} for k in 0..=n, e.g. a key built from
+    /// the upper half of a single powers-of-tau transcript
+    fn check_hiding_generator(&self) -> Result<(), LaconicError> {
+        let n = self.domain.size();
+        let Ok(h1) = self.h1() else {
+            return Ok(());
+        };
+        if h1.is_zero()
+            || self.u.contains(&h1)
+            || E::pairing(h1, self.g2) == E::pairing(self.u[n - 1], self.r)
+        {
+            return Err(LaconicError::InvalidCommitmentKey("generators"));
+        }
+        Ok(())
+    }
+
+    /// checks the parts of verify that do not involve group operations
+    fn check_shape(&self) -> Result<(), LaconicError> {
+        let domain = D::new(self.message_length.max(2));
+        if self.message_length < 1 || domain != Some(self.domain) {
+            return Err(LaconicError::InvalidCommitmentKey("domain"));
+        }
+        let n = self.domain.size();
+        if self.u.len() != n
+            || (self.hat_u.len() != n && !self.hat_u.is_empty())
+            || self.lagranges.len() != n + self.hat_u.len()
+            || self.d.len() != self.message_length
+            || self.y.len() != 2 * n
+        {
+            return Err(LaconicError::InvalidCommitmentKey("length"));
+        }
+        // with r = 1 every power is 1, which is a valid chain of powers
+        if self.u[0].is_zero() || self.g2.is_zero() || self.r.is_zero() {
+            return Err(LaconicError::InvalidCommitmentKey("generators"));
+        }
+        Ok(())
+    }
+}
+
+impl<E: CurveId> MultiOpening<E> {
+    /// Encode the opening in the wire format
+    pub fn to_bytes<D: EvaluationDomain<E::ScalarField>>(
+        &self,
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Vec<u8>, LaconicError> {
+        encode::<E, _>(Kind::MultiOpening, ck.domain.size(), self)
+    }
+
+    /// Decode an opening produced for the same commitment key
+    pub fn from_bytes<D: EvaluationDomain<E::ScalarField>>(
+        bytes: &[u8],
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Self, LaconicError> {
+        let (domain_size, opening) = decode::<E, _>(Kind::MultiOpening, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        Ok(opening)
+    }
+}
+
+/// lagranges[i] = g1^{l_i(alpha)} and lagranges[n + i] = h1^{l_i(alpha)},
+/// where the latter are missing if hat_u is empty
+fn lagranges_from_powers<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    domain: &D,
+    u: &[E::G1Affine],
+    hat_u: &[E::G1Affine],
+) -> Vec<E::G1Affine> {
+    let mut lagranges = Vec::with_capacity(u.len() + hat_u.len());
+    for powers in [u, hat_u].into_iter().filter(|p| !p.is_empty()) {
+        let proj: Vec<E::G1> = powers.iter().map(|p| p.into_group()).collect();
+        lagranges.extend(E::G1::normalize_batch(&domain.ifft(&proj)));
+    }
+    lagranges
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::Bls12_381;
+    use ark_ec::pairing::Pairing;
+    use ark_ec::{AffineRepr, CurveGroup, Group};
+    use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+    use ark_std::UniformRand;
+    use std::ops::Mul;
+
+    use super::{CommitmentKey, VcKZG};
+    use crate::error::LaconicError;
+    use crate::{LaconicOTRecv, LaconicOTSender};
+
+    type F = <Bls12_381 as Pairing>::ScalarField;
+    type G1 = <Bls12_381 as Pairing>::G1;
+    type G2 = <Bls12_381 as Pairing>::G2;
+    type G1Affine = <Bls12_381 as Pairing>::G1Affine;
+    type G2Affine = <Bls12_381 as Pairing>::G2Affine;
+    type D = Radix2EvaluationDomain<F>;
+
+    /// test functions to_bytes and from_bytes
+    #[test]
+    fn test_to_bytes() {
+        use ark_serialize::CanonicalSerialize;
+
+        let mut rng = ark_std::test_rng();
+        let ck = CommitmentKey::<Bls12_381, D>::setup(&mut rng, 8).unwrap();
+        let bytes = ck.to_bytes().unwrap();
+        assert_eq!(bytes.len(), ck.compressed_size() + crate::wire::HEADER_SIZE);
+        assert!(bytes.len() < ck.uncompressed_size());
+        assert_eq!(CommitmentKey::from_bytes(&bytes), Ok(ck.clone()));
+
+        // header claims a different domain size
+        let mut bad = bytes.clone();
+        bad[7] = 16;
+        assert_eq!(
+            CommitmentKey::<Bls12_381, D>::from_bytes(&bad),
+            Err(LaconicError::DomainMismatch {
+                expected: 8,
+                found: 16
+            })
+        );
+
+        // inconsistent lengths
+        let mut bad = ck.clone();
+        bad.d.pop();
+        let bytes = bad.to_bytes().unwrap();
+        assert_eq!(
+            CommitmentKey::<Bls12_381, D>::from_bytes(&bytes),
+            Err(LaconicError::InvalidCommitmentKey("length"))
+        );
+    }
+
+    /// test functions verify and verify_batched
+    #[test]
+    fn test_verify() {
+        let mut rng = ark_std::test_rng();
+        let ck = CommitmentKey::<Bls12_381, D>::setup(&mut rng, 7).unwrap();
+        assert_eq!(ck.verify(), Ok(()));
+        assert_eq!(ck.verify_batched(&mut rng), Ok(()));
+
+        let check = |bad: CommitmentKey<Bls12_381, D>, component: &'static str| {
+            let mut rng = ark_std::test_rng();
+            let expected = Err(LaconicError::InvalidCommitmentKey(component));
+            assert_eq!(bad.verify(), expected);
+            assert_eq!(bad.verify_batched(&mut rng), expected);
+        };
+
+        let mut bad = ck.clone();
+        bad.u[3] = (bad.u[3] + bad.u[0]).into_affine();
+        check(bad, "powers");
+
+        let mut bad = ck.clone();
+        bad.hat_u[5] = bad.u[5];
+        check(bad, "powers");
+
+        let mut bad = ck.clone();
+        bad.lagranges.swap(1, 2);
+        check(bad, "lagranges");
+
+        let mut bad = ck.clone();
+        bad.d[6] = bad.r;
+        check(bad, "d");
+
+        let mut bad = ck.clone();
+        bad.y[0] = bad.u[0];
+        check(bad, "y");
+
+        let mut bad = ck.clone();
+        bad.d.pop();
+        check(bad, "length");
+
+        let mut bad = ck.clone();
+        bad.message_length = 9;
+        check(bad, "domain");
+
+        // degenerate keys with alpha = 0 or h1 = 1
+        let mut bad = ck.clone();
+        bad.r = G2Affine::zero();
+        for p in bad.u[1..].iter_mut().chain(&mut bad.hat_u[1..]) {
+            *p = G1Affine::zero();
+        }
+        check(bad, "generators");
+
+        let mut bad = ck.clone();
+        bad.hat_u = vec![G1Affine::zero(); bad.hat_u.len()];
+        let n = bad.domain.size();
+        bad.lagranges[n..].fill(G1Affine::zero());
+        check(bad, "generators");
+    }
+
+    /// test function from_hiding_powers_of_tau
+    #[test]
+    fn test_from_hiding_powers_of_tau() {
+        let mut rng = ark_std::test_rng();
+        let message_length = 7;
+
+        // simulate the output of a ceremony
+        let alpha = F::rand(&mut rng);
+        let h = G1::rand(&mut rng);
+        let mut powers = Vec::new();
+        let mut hat_powers = Vec::new();
+        let (mut curr, mut curr_h) = (G1::generator(), h);
+        for _ in 0..16 {
+            powers.push(curr.into_affine());
+            hat_powers.push(curr_h.into_affine());
+            curr = curr.mul(alpha);
+            curr_h = curr_h.mul(alpha);
+        }
+        let g2 = G2::generator();
+        let r = g2.mul(alpha).into_affine();
+
+        let ck = CommitmentKey::<Bls12_381, D>::from_hiding_powers_of_tau(
+            message_length,
+            &powers,
+            &hat_powers,
+            g2.into_affine(),
+            r,
+        )
+        .unwrap();
+
+        // compare against the values computed with knowledge of alpha
+        let lf = ck.domain.evaluate_all_lagrange_coefficients(alpha);
+        for i in 0..ck.domain.size() {
+            assert_eq!(ck.lagranges[i], powers[0].mul(lf[i]).into_affine());
+            assert_eq!(ck.hat_u[i], hat_powers[i]);
+        }
+        for i in 0..message_length {
+            let exponent = alpha - ck.domain.element(i);
+            assert_eq!(ck.d[i], g2.mul(exponent).into_affine());
+        }
+
+        assert_eq!(ck.verify(), Ok(()));
+
+        // the key works for laconic OT
+        let receiver = LaconicOTRecv::new(&ck, &[true, false, true]).unwrap();
+        let sender = LaconicOTSender::new(&ck, receiver.commitment());
+        let msg = sender.send(&mut rng, 2, &[0u8; 16], &[1u8; 16]).unwrap();
+        assert_eq!(receiver.recv(2, &msg).unwrap(), vec![1u8; 16]);
+
+        // the transcript must cover the domain
+        let res = CommitmentKey::<Bls12_381, D>::from_hiding_powers_of_tau(
+            message_length,
+            &powers,
+            &hat_powers[..7],
+            g2.into_affine(),
+            r,
+        );
+        assert_eq!(
+            res.err(),
+            Some(LaconicError::NotEnoughPowers {
+                available: 7,
+                required: 8
+            })
+        );
+
+        // h1 = g1^{alpha^8} is known relative to the powers of g1,
+        // which would allow to open hiding commitments to anything
+        let res = CommitmentKey::<Bls12_381, D>::from_hiding_powers_of_tau(
+            message_length,
+            &powers,
+            &powers[8..],
+            g2.into_affine(),
+            r,
+        );
+        assert_eq!(
+            res.err(),
+            Some(LaconicError::InvalidCommitmentKey("generators"))
+        );
+        let mut bad = ck.clone();
+        bad.hat_u = powers[8..].to_vec();
+        bad.lagranges =
+            super::lagranges_from_powers::<Bls12_381, D>(&bad.domain, &bad.u, &bad.hat_u);
+        assert_eq!(
+            bad.verify(),
+            Err(LaconicError::InvalidCommitmentKey("generators"))
+        );
+    }
+
+    /// test function from_powers_of_tau with a plain transcript
+    #[test]
+    fn test_from_plain_powers_of_tau() {
+        let mut rng = ark_std::test_rng();
+
+        // a public transcript only has powers of g1 and g2^{alpha}
+        let alpha = F::rand(&mut rng);
+        let mut powers = Vec::new();
+        let mut curr = G1::generator();
+        for _ in 0..8 {
+            powers.push(curr.into_affine());
+            curr = curr.mul(alpha);
+        }
+        let g2 = G2::generator().into_affine();
+        let r = g2.mul(alpha).into_affine();
+
+        let ck = CommitmentKey::<Bls12_381, D>::from_powers_of_tau(5, &powers, g2, r).unwrap();
+        assert!(ck.hat_u.is_empty());
+        assert_eq!(ck.lagranges.len(), ck.domain.size());
+        assert_eq!(ck.verify(), Ok(()));
+        assert_eq!(ck.verify_batched(&mut rng), Ok(()));
+        assert_eq!(
+            CommitmentKey::from_bytes(&ck.to_bytes().unwrap()),
+            Ok(ck.clone())
+        );
+
+        // the key works for laconic OT
+        let mut receiver = LaconicOTRecv::new(&ck, &[true, false, true]).unwrap();
+        let sender = LaconicOTSender::new(&ck, receiver.commitment());
+        let msg = sender.send(&mut rng, 0, &[0u8; 16], &[1u8; 16]).unwrap();
+        assert_eq!(receiver.recv(0, &msg).unwrap(), vec![1u8; 16]);
+
+        // but not for anything that needs the hiding part
+        assert_eq!(ck.h1(), Err(LaconicError::NotHiding));
+        assert_eq!(
+            VcKZG::commit(&mut rng, &ck, &[F::from(1u64)]).err(),
+            Some(LaconicError::NotHiding)
+        );
+        assert_eq!(
+            receiver.prove_bits(&mut rng).err(),
+            Some(LaconicError::NotHiding)
+        );
+
+        // and a hiding part cannot be added to it later
+        let mut bad = ck.clone();
+        bad.hat_u = powers[..ck.domain.size()].to_vec();
+        assert_eq!(
+            bad.verify(),
+            Err(LaconicError::InvalidCommitmentKey("length"))
+        );
+    }
 }
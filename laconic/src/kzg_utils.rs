@@ -1,11 +1,13 @@
 use ark_ec::{pairing::Pairing, AffineRepr, CurveGroup, VariableBaseMSM};
-use ark_ff::{batch_inversion, Field};
-use ark_poly::{EvaluationDomain, Polynomial};
+use ark_ff::{batch_inversion, PrimeField};
+use ark_poly::univariate::{DenseOrSparsePolynomial, DensePolynomial};
+use ark_poly::{DenseUVPolynomial, EvaluationDomain, Polynomial};
 use ark_serialize::CanonicalSerialize;
-use ark_std::{One, Zero};
+use ark_std::{One, UniformRand, Zero};
 use std::ops::Mul;
 
-use crate::kzg_types::{Commitment, CommitmentKey, Opening};
+use crate::error::LaconicError;
+use crate::kzg_types::{CommitmentKey, MultiOpening, Opening};
 
 // This module contains helper functions for the Simulation Extractable KZG Vector commitment
 
@@ -21,6 +23,10 @@ pub fn plain_kzg_verify<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
     // check e(com*g1^{-y}*h^{-hat_y},g2) == e(v,r*g2^{-z})
     let mut lhs_left = com_kzg.into_group();
     lhs_left -= ck.u[0].mul(y);
+    let Some(mask) = masking(ck, tau.hat_y) else {
+        return false;
+    };
+    lhs_left -= mask;
     let rhs_right = ck.r.into_group() - ck.g2.mul(z);
     // Naive Implementation:
     //  let lhs = E::pairing(lhs_left, ck.g2);
@@ -46,6 +52,10 @@ pub fn plain_kzg_verify_inside<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
     // check e(com*g1^{-y}*h^{-hat_y0},g2) == e(v0,r*g2^{-z0})
     let mut lhs_left = com_kzg.into_group();
     lhs_left -= ck.u[0].mul(y);
+    let Some(mask) = masking(ck, tau.hat_y) else {
+        return false;
+    };
+    lhs_left -= mask;
     // Naive Implementation:
     //  let lhs = E::pairing(lhs_left, ck.g2);
     //  let rhs = E::pairing(tau.v, ck.d[i]);
@@ -57,17 +67,285 @@ pub fn plain_kzg_verify_inside<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
     q.is_zero()
 }
 
+/// KZG multi-opening of f at the domain points with the given indices,
+/// see MultiOpening. evals are the evaluations of f over the domain,
+/// optionally followed by those of the masking polynomial.
+/// Assumes that the indices are distinct and in the domain, see check_indices
+pub fn plain_kzg_multi_open<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    ck: &CommitmentKey<E, D>,
+    com_kzg: &E::G1Affine,
+    evals: &[E::ScalarField],
+    indices: &[usize],
+) -> MultiOpening<E> {
+    let n = ck.domain.size();
+    let points: Vec<_> = indices.iter().map(|i| ck.domain.element(*i)).collect();
+    let values: Vec<_> = indices.iter().map(|i| evals[*i]).collect();
+
+    // Z_S = prod_i (X - w_i)
+    let mut z_s = DensePolynomial::from_coefficients_vec(vec![E::ScalarField::one()]);
+    for w in points.iter() {
+        z_s = z_s.naive_mul(&DensePolynomial::from_coefficients_vec(vec![
+            -*w,
+            E::ScalarField::one(),
+        ]));
+    }
+
+    // coefficient form of f and the masking polynomial hat_f
+    let f = DensePolynomial::from_coefficients_vec(ck.domain.ifft(&evals[0..n]));
+    let hat_f = if evals.len() > n {
+        DensePolynomial::from_coefficients_vec(ck.domain.ifft(&evals[n..2 * n]))
+    } else {
+        DensePolynomial::zero()
+    };
+
+    // f = q * Z_S + I_S, so the quotient is (f - I_S) / Z_S
+    let q = divide::<E>(&f, &z_s);
+    let hat_q = divide::<E>(&hat_f, &z_s);
+    let w = (kzg_com_coeffs::<E>(&ck.u, &q) + kzg_com_coeffs::<E>(&ck.hat_u, &hat_q)).into_affine();
+
+    // the linearized polynomials take the values I_S(z) and hat_y at z
+    let z = multi_challenge::<E>(com_kzg, indices, &values, &w);
+    let zs_z = z_s.evaluate(&z);
+    let l = &f - &(&q * zs_z);
+    let hat_l = &hat_f - &(&hat_q * zs_z);
+    let hat_y = hat_l.evaluate(&z);
+
+    // standard KZG opening of both at z, the remainders are dropped
+    let x_minus_z = DensePolynomial::from_coefficients_vec(vec![-z, E::ScalarField::one()]);
+    let psi = divide::<E>(&l, &x_minus_z);
+    let hat_psi = divide::<E>(&hat_l, &x_minus_z);
+    let v = kzg_com_coeffs::<E>(&ck.u, &psi) + kzg_com_coeffs::<E>(&ck.hat_u, &hat_psi);
+
+    MultiOpening {
+        w,
+        v: v.into_affine(),
+        hat_y,
+    }
+}
+
+/// Verify a multi-opening proving f(w_i) = values[k] for i = indices[k].
+/// Only needs g1, h1, g2 and r from the commitment key
+pub fn plain_kzg_multi_verify<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    ck: &CommitmentKey<E, D>,
+    com_kzg: &E::G1Affine,
+    indices: &[usize],
+    values: &[E::ScalarField],
+    opening: &MultiOpening<E>,
+) -> bool {
+    if indices.len() != values.len() || check_indices(indices, ck.domain.size()).is_err() {
+        return false;
+    }
+    let points: Vec<_> = indices.iter().map(|i| ck.domain.element(*i)).collect();
+    let z = multi_challenge::<E>(com_kzg, indices, values, &opening.w);
+
+    // Z_S(z) and I_S(z) = sum_k values[k] * prod_{l != k} (z - w_l) / (w_k - w_l)
+    let zs_z: E::ScalarField = points.iter().map(|w| z - w).product();
+    let mut nums = Vec::with_capacity(points.len());
+    let mut denoms = Vec::with_capacity(points.len());
+    for (k, wk) in points.iter().enumerate() {
+        let mut num = E::ScalarField::one();
+        let mut denom = E::ScalarField::one();
+        for (_, wl) in points.iter().enumerate().filter(|(l, _)| *l != k) {
+            num *= z - wl;
+            denom *= *wk - wl;
+        }
+        nums.push(num);
+        denoms.push(denom);
+    }
+    batch_inversion(&mut denoms);
+    let is_z: E::ScalarField = values
+        .iter()
+        .zip(nums.iter().zip(denoms.iter()))
+        .map(|(y, (num, denom))| *y * num * denom)
+        .sum();
+
+    // check e(com*g1^{-I_S(z)}*h1^{-hat_y}*w^{-Z_S(z)},g2) == e(v,r*g2^{-z})
+    let mut lhs_left = com_kzg.into_group();
+    lhs_left -= ck.u[0].mul(is_z);
+    let Some(mask) = masking(ck, opening.hat_y) else {
+        return false;
+    };
+    lhs_left -= mask;
+    lhs_left -= opening.w.mul(zs_z);
+    let rhs_right = ck.r.into_group() - ck.g2.mul(z);
+    let left = vec![
+        E::G1Prepared::from(-lhs_left),
+        E::G1Prepared::from(opening.v),
+    ];
+    let right = vec![E::G2Prepared::from(ck.g2), E::G2Prepared::from(rhs_right)];
+    E::multi_pairing(left, right).is_zero()
+}
+
+/// h1^{hat_y}, or None if hat_y is nonzero
+/// but the key has no hiding part
+fn masking<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    ck: &CommitmentKey<E, D>,
+    hat_y: E::ScalarField,
+) -> Option<E::G1> {
+    match ck.hat_u.first() {
+        Some(h1) => Some(h1.mul(hat_y)),
+        None if hat_y.is_zero() => Some(E::G1::zero()),
+        None => None,
+    }
+}
+
+/// Check that all indices are smaller than length and distinct
+pub fn check_indices(indices: &[usize], length: usize) -> Result<(), LaconicError> {
+    let mut sorted = indices.to_vec();
+    sorted.sort_unstable();
+    for (k, index) in sorted.iter().enumerate() {
+        if *index >= length {
+            return Err(LaconicError::IndexOutOfRange {
+                index: *index,
+                length,
+            });
+        }
+        if k > 0 && sorted[k - 1] == *index {
+            return Err(LaconicError::DuplicateIndex { index: *index });
+        }
+    }
+    Ok(())
+}
+
+/// Fiat-Shamir challenge for a multi-opening,
+/// binding the statement and the quotient commitment w
+fn multi_challenge<E: Pairing>(
+    com_kzg: &E::G1Affine,
+    indices: &[usize],
+    values: &[E::ScalarField],
+    w: &E::G1Affine,
+) -> E::ScalarField {
+    let mut hsh = blake3::Hasher::new();
+    hsh.update(b"laconic-ot multi-opening");
+    com_kzg.serialize_compressed(&mut hsh).unwrap();
+    hsh.update(&(indices.len() as u64).to_le_bytes());
+    for (i, y) in indices.iter().zip(values) {
+        hsh.update(&(*i as u64).to_le_bytes());
+        y.serialize_compressed(&mut hsh).unwrap();
+    }
+    w.serialize_compressed(&mut hsh).unwrap();
+
+    // reduce 64 bytes to make the bias negligible
+    let mut bytes = [0u8; 64];
+    hsh.finalize_xof().fill(&mut bytes);
+    E::ScalarField::from_le_bytes_mod_order(&bytes)
+}
+
+/// Fiat-Shamir challenge for the transcript, separated by label
+pub fn fiat_shamir<E: Pairing>(
+    label: &[u8],
+    transcript: &impl CanonicalSerialize,
+) -> E::ScalarField {
+    let mut hsh = blake3::Hasher::new();
+    hsh.update(label);
+    transcript.serialize_compressed(&mut hsh).unwrap();
+
+    // reduce 64 bytes to make the bias negligible
+    let mut bytes = [0u8; 64];
+    hsh.finalize_xof().fill(&mut bytes);
+    E::ScalarField::from_le_bytes_mod_order(&bytes)
+}
+
+/// Split a quotient t of degree up to 2n - 2 as t = t_lo + X^{n-1} t_hi,
+/// so that both parts can be committed with the n powers of the key.
+/// As in PLONK, the split is blinded with a random beta,
+/// i.e., the parts are t_lo + beta * X^{n-1} and t_hi - beta
+pub fn split_quotient<E: Pairing, R: rand::Rng>(
+    rng: &mut R,
+    t: &DensePolynomial<E::ScalarField>,
+    n: usize,
+) -> (
+    DensePolynomial<E::ScalarField>,
+    DensePolynomial<E::ScalarField>,
+) {
+    let beta = E::ScalarField::rand(rng);
+    let mut lo = t.coeffs.clone();
+    lo.resize(lo.len().max(n - 1), E::ScalarField::zero());
+    let mut hi = lo.split_off(n - 1);
+    lo.push(beta);
+    if hi.is_empty() {
+        hi.push(E::ScalarField::zero());
+    }
+    hi[0] -= beta;
+    (
+        DensePolynomial::from_coefficients_vec(lo),
+        DensePolynomial::from_coefficients_vec(hi),
+    )
+}
+
+/// quotient of the polynomial division p / d
+pub fn divide<E: Pairing>(
+    p: &DensePolynomial<E::ScalarField>,
+    d: &DensePolynomial<E::ScalarField>,
+) -> DensePolynomial<E::ScalarField> {
+    let (q, _) = DenseOrSparsePolynomial::from(p)
+        .divide_with_q_and_r(&d.into())
+        .unwrap();
+    q
+}
+
+/// KZG commitment to a polynomial in coefficient form
+pub fn kzg_com_coeffs<E: Pairing>(
+    powers: &[E::G1Affine],
+    p: &DensePolynomial<E::ScalarField>,
+) -> E::G1 {
+    <E::G1 as VariableBaseMSM>::msm(&powers[0..p.coeffs.len()], &p.coeffs).unwrap()
+}
+
+/// Check that powers[i] = powers[0]^{alpha^i}, where r = g2^{alpha}.
+/// That is, e(powers[i+1], g2) = e(powers[i], r) for all i.
+/// All equations are combined with random coefficients, so this
+/// costs two MSMs and a single multi-pairing instead of 2n pairings
+pub fn check_powers<E: Pairing, R: rand::Rng>(
+    rng: &mut R,
+    powers: &[E::G1Affine],
+    g2: E::G2Affine,
+    r: E::G2Affine,
+) -> bool {
+    if powers.len() < 2 {
+        return true;
+    }
+    let n = powers.len() - 1;
+    let rho: Vec<E::ScalarField> = (0..n).map(|_| E::ScalarField::rand(rng)).collect();
+    let lhs = <E::G1 as VariableBaseMSM>::msm(&powers[1..], &rho).unwrap();
+    let rhs = <E::G1 as VariableBaseMSM>::msm(&powers[..n], &rho).unwrap();
+    let left = vec![E::G1Prepared::from(lhs), E::G1Prepared::from(-rhs)];
+    let right = vec![E::G2Prepared::from(g2), E::G2Prepared::from(r)];
+    E::multi_pairing(left, right).is_zero()
+}
+
 /// Compute a KZG commitment for the given vector of evaluations
 #[inline]
 pub fn plain_kzg_com<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
     ck: &CommitmentKey<E, D>,
     evals: &[E::ScalarField],
 ) -> E::G1Affine {
-    assert_eq!(evals.len(), ck.lagranges.len());
-    let c = <E::G1 as VariableBaseMSM>::msm(&ck.lagranges, evals).unwrap();
+    // either only the polynomial, or also the masking polynomial
+    assert!(evals.len() == ck.domain.size() || evals.len() == ck.lagranges.len());
+    let c = <E::G1 as VariableBaseMSM>::msm(&ck.lagranges[0..evals.len()], evals).unwrap();
     c.into_affine()
 }
 
+/// Deserialize an uncompressed curve point and check that it is valid.
+/// Unlike the checked deserialization of arkworks, this tells apart
+/// points that are not on the curve from points that are on the curve
+/// but not in the prime order subgroup
+pub fn deserialize_point<P: AffineRepr>(bytes: &[u8]) -> Result<P, LaconicError> {
+    let p =
+        P::deserialize_uncompressed_unchecked(bytes).map_err(|_| LaconicError::MalformedPoint)?;
+    if p.check().is_ok() {
+        return Ok(p);
+    }
+    // the check failed. To find out why, we recompute y from x
+    // by decompression, which only gives back p if p is on the curve
+    let mut compressed = Vec::new();
+    p.serialize_compressed(&mut compressed)?;
+    match P::deserialize_compressed_unchecked(compressed.as_slice()) {
+        Ok(q) if q == p => Err(LaconicError::SubgroupCheckFailed),
+        _ => Err(LaconicError::MalformedPoint),
+    }
+}
+
 /// Check if the given element is in the evaluation domain
 /// and if so, return the index of it. Otherwise, return None
 #[inline]
@@ -114,9 +392,9 @@ pub fn witness_evals_inside<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
     let xi = domain.element(i);
     let mut nums = Vec::new();
     let mut denoms = Vec::new();
-    for j in 0..domain.size() {
+    for (j, fxj) in evals.iter().enumerate().take(domain.size()) {
         // f(x_j) - f(x_i)
-        nums.push(evals[j] - fxi);
+        nums.push(*fxj - fxi);
         // x_j-x_i
         denoms.push(domain.element(j) - xi);
     }
@@ -209,7 +487,6 @@ pub fn evaluate_outside<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
 mod tests {
     use ark_bls12_381::Bls12_381;
     use ark_ec::pairing::Pairing;
-    use ark_ec::CurveGroup;
     use ark_poly::univariate::DensePolynomial;
     use ark_poly::{DenseUVPolynomial, Radix2EvaluationDomain};
     use ark_poly::{EvaluationDomain, Evaluations, Polynomial};
@@ -217,12 +494,60 @@ mod tests {
     use ark_std::UniformRand;
 
     use super::{
-        evaluate_outside, find_in_domain, inv_diffs, witness_evals_inside, witness_evals_outside,
+        deserialize_point, evaluate_outside, find_in_domain, inv_diffs, witness_evals_inside,
+        witness_evals_outside,
     };
+    use crate::error::LaconicError;
 
     type F = <Bls12_381 as Pairing>::ScalarField;
     type D = Radix2EvaluationDomain<F>;
 
+    /// test function deserialize_point
+    #[test]
+    fn test_deserialize_point() {
+        use ark_bls12_381::{Fq, G1Affine};
+        use ark_serialize::CanonicalSerialize;
+
+        let mut rng = ark_std::rand::thread_rng();
+
+        // valid points are accepted
+        let p = G1Affine::rand(&mut rng);
+        let mut bytes = Vec::new();
+        p.serialize_uncompressed(&mut bytes).unwrap();
+        assert_eq!(deserialize_point::<G1Affine>(&bytes), Ok(p));
+
+        // points off the curve are rejected
+        let mut off_curve = bytes.clone();
+        off_curve[95] ^= 1;
+        assert_eq!(
+            deserialize_point::<G1Affine>(&off_curve),
+            Err(LaconicError::MalformedPoint)
+        );
+
+        // truncated encodings are rejected
+        assert_eq!(
+            deserialize_point::<G1Affine>(&bytes[..40]),
+            Err(LaconicError::MalformedPoint)
+        );
+
+        // points on the curve, but outside the subgroup are rejected
+        let mut x = Fq::one();
+        let q = loop {
+            if let Some(q) = G1Affine::get_point_from_x_unchecked(x, true) {
+                if !q.is_in_correct_subgroup_assuming_on_curve() {
+                    break q;
+                }
+            }
+            x += Fq::one();
+        };
+        let mut bytes = Vec::new();
+        q.serialize_uncompressed(&mut bytes).unwrap();
+        assert_eq!(
+            deserialize_point::<G1Affine>(&bytes),
+            Err(LaconicError::SubgroupCheckFailed)
+        );
+    }
+
     /// test function find_in_domain
     #[test]
     fn test_find_in_domain() {
@@ -289,9 +614,9 @@ mod tests {
             // compute its inverse differences
             let inv_diffs = inv_diffs::<Bls12_381, D>(&domain, z);
             // check that each element is really the inverse
-            for i in 0..domain.size() {
+            for (i, inv) in inv_diffs.iter().enumerate() {
                 let diff = domain.element(i) - z;
-                let prod = diff * inv_diffs[i];
+                let prod = diff * inv;
                 assert_eq!(prod, F::one());
             }
         }
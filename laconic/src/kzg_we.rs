@@ -0,0 +1,253 @@
+use ark_ec::pairing::Pairing;
+use ark_ec::{AffineRepr, CurveGroup};
+use ark_poly::EvaluationDomain;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::{UniformRand, Zero};
+use std::ops::Mul;
+
+use crate::error::LaconicError;
+use crate::kzg_types::{CommitmentKey, Opening};
+use crate::kzg_utils::{
+    evaluate_outside, find_in_domain, inv_diffs, plain_kzg_com, plain_kzg_verify,
+    witness_evals_inside, witness_evals_outside,
+};
+use crate::laconic_ot::{decrypt as decrypt_pad, encrypt as encrypt_pad};
+use crate::wire::{check_domain_size, decode, encode, CurveId, Kind};
+
+// This module implements witness encryption for KZG commitments.
+// A message is encrypted towards (com, z, y), and anyone with an
+// opening proving f(z) = y for the polynomial f in com can decrypt.
+// With r random, the ciphertext is h = g2^{r(alpha - z)} together with
+// the message encrypted under e(com * g1^{-y}, g2)^r = e(v, h),
+// where v is the opening. Laconic OT is the special case of
+// z in the evaluation domain and y in {0, 1}.
+
+/// Witness encryption of a message towards (com, z, y)
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug, PartialEq, Eq)]
+pub struct Ciphertext<E: Pairing> {
+    /// the statement, which is needed to authenticate the ciphertext
+    pub com: E::G1Affine,
+    pub z: E::ScalarField,
+    pub y: E::ScalarField,
+
+    /// h = g2^{r(alpha - z)}
+    h: E::G2Affine,
+
+    /// the masked message followed by the tag
+    ct: Vec<u8>,
+}
+
+/// binds a ciphertext to the statement (com, z, y)
+fn associated_data<E: Pairing>(
+    com: &E::G1Affine,
+    z: &E::ScalarField,
+    y: &E::ScalarField,
+) -> Vec<u8> {
+    let mut ad = Vec::new();
+    com.serialize_compressed(&mut ad).unwrap();
+    z.serialize_compressed(&mut ad).unwrap();
+    y.serialize_compressed(&mut ad).unwrap();
+    ad
+}
+
+/// Commit to the polynomial with the given evaluations over the domain.
+/// Missing evaluations are set to zero.
+/// The commitment is deterministic and not hiding: anyone can check a
+/// guess of the evaluations by recomputing it, so they should have
+/// enough entropy if they are secret
+pub fn commit<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    ck: &CommitmentKey<E, D>,
+    evals: &[E::ScalarField],
+) -> Result<E::G1Affine, LaconicError> {
+    let evals = pad_evals(ck, evals)?;
+    Ok(plain_kzg_com(ck, &evals))
+}
+
+/// Evaluate the polynomial with the given evaluations at z
+/// and compute an opening proving the result.
+/// z can be inside or outside the evaluation domain
+pub fn open<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    ck: &CommitmentKey<E, D>,
+    evals: &[E::ScalarField],
+    z: E::ScalarField,
+) -> Result<(E::ScalarField, Opening<E>), LaconicError> {
+    let evals = pad_evals(ck, evals)?;
+    let mut witn_evals = Vec::with_capacity(ck.domain.size());
+    let y = match find_in_domain::<E, D>(&ck.domain, z) {
+        Some(i) => {
+            witness_evals_inside::<E, D>(&ck.domain, &evals, i, &mut witn_evals);
+            evals[i]
+        }
+        None => {
+            let inv_diffs = inv_diffs::<E, D>(&ck.domain, z);
+            let y = evaluate_outside::<E, D>(&ck.domain, &evals, z, &inv_diffs);
+            witness_evals_outside::<E, D>(&ck.domain, &evals, y, &inv_diffs, &mut witn_evals);
+            y
+        }
+    };
+    let v = plain_kzg_com(ck, &witn_evals);
+    Ok((
+        y,
+        Opening {
+            v,
+            hat_y: E::ScalarField::zero(),
+        },
+    ))
+}
+
+/// Verify that the opening proves f(z) = y for com
+pub fn verify<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    ck: &CommitmentKey<E, D>,
+    com: &E::G1Affine,
+    z: E::ScalarField,
+    y: E::ScalarField,
+    opening: &Opening<E>,
+) -> bool {
+    // the commitments have no hiding part, with hat_y != 0 the
+    // opening could be for com * h1^{-hat_y} and would not decrypt
+    opening.hat_y.is_zero() && plain_kzg_verify(ck, com, z, y, opening)
+}
+
+/// Encrypt msg such that it can be decrypted
+/// with an opening proving f(z) = y for com
+pub fn encrypt<E: Pairing, D: EvaluationDomain<E::ScalarField>, R: rand::Rng>(
+    rng: &mut R,
+    ck: &CommitmentKey<E, D>,
+    com: &E::G1Affine,
+    z: E::ScalarField,
+    y: E::ScalarField,
+    msg: &[u8],
+) -> Ciphertext<E> {
+    let r = E::ScalarField::rand(rng);
+
+    // g2^{alpha - z} is precomputed for the first points of the domain
+    let d = match find_in_domain::<E, D>(&ck.domain, z) {
+        Some(i) if i < ck.d.len() => ck.d[i].into_group(),
+        _ => ck.r.into_group() - ck.g2.mul(z),
+    };
+    let h = d * r;
+
+    // e(com * g1^{-y}, g2)^r
+    let l = (com.into_group() - ck.u[0].mul(y)) * r;
+    let pad = E::pairing(l, ck.g2);
+
+    let ad = associated_data::<E>(com, &z, &y);
+    Ciphertext {
+        com: *com,
+        z,
+        y,
+        h: h.into_affine(),
+        ct: encrypt_pad::<E>(pad.0, &ad, msg),
+    }
+}
+
+/// Decrypt the ciphertext with an opening for its statement
+pub fn decrypt<E: Pairing>(
+    opening: &Opening<E>,
+    ct: &Ciphertext<E>,
+) -> Result<Vec<u8>, LaconicError> {
+    if !opening.hat_y.is_zero() {
+        return Err(LaconicError::InvalidOpening);
+    }
+    let pad = E::pairing(opening.v, ct.h);
+    let ad = associated_data::<E>(&ct.com, &ct.z, &ct.y);
+    decrypt_pad::<E>(pad.0, &ad, &ct.ct)
+}
+
+impl<E: CurveId> Ciphertext<E> {
+    /// Encode the ciphertext in the wire format
+    pub fn to_bytes<D: EvaluationDomain<E::ScalarField>>(
+        &self,
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Vec<u8>, LaconicError> {
+        encode::<E, _>(Kind::WeCiphertext, ck.domain.size(), self)
+    }
+
+    /// Decode a ciphertext produced for the same commitment key
+    pub fn from_bytes<D: EvaluationDomain<E::ScalarField>>(
+        bytes: &[u8],
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Self, LaconicError> {
+        let (domain_size, ct) = decode::<E, _>(Kind::WeCiphertext, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        Ok(ct)
+    }
+}
+
+/// extend the evaluations with zeros to the size of the domain
+fn pad_evals<E: Pairing, D: EvaluationDomain<E::ScalarField>>(
+    ck: &CommitmentKey<E, D>,
+    evals: &[E::ScalarField],
+) -> Result<Vec<E::ScalarField>, LaconicError> {
+    let size = ck.domain.size();
+    if evals.len() > size {
+        return Err(LaconicError::MessageTooLong {
+            length: evals.len(),
+            max: size,
+        });
+    }
+    let mut evals = evals.to_vec();
+    evals.resize(size, E::ScalarField::zero());
+    Ok(evals)
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_ec::CurveGroup;
+    use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+    use ark_std::{test_rng, One, UniformRand};
+
+    use super::{commit, decrypt, encrypt, open, verify, Ciphertext};
+    use crate::error::LaconicError;
+    use crate::kzg_types::CommitmentKey;
+    use crate::kzg_utils::plain_kzg_verify;
+
+    type D = Radix2EvaluationDomain<Fr>;
+
+    #[test]
+    fn test_kzg_we() {
+        let rng = &mut test_rng();
+        let ck = CommitmentKey::<Bls12_381, D>::setup(rng, 8).unwrap();
+
+        let evals: Vec<Fr> = (0..6).map(|_| Fr::rand(rng)).collect();
+        let com = commit(&ck, &evals).unwrap();
+
+        // z inside and outside the domain
+        for z in [ck.domain.element(3), ck.domain.element(7), Fr::rand(rng)] {
+            let (y, opening) = open(&ck, &evals, z).unwrap();
+            assert!(verify(&ck, &com, z, y, &opening));
+            assert!(!verify(&ck, &com, z, y + Fr::from(1u64), &opening));
+            let ct = encrypt(rng, &ck, &com, z, y, b"hello");
+            assert_eq!(decrypt(&opening, &ct).unwrap(), b"hello");
+
+            let ct = Ciphertext::from_bytes(&ct.to_bytes(&ck).unwrap(), &ck).unwrap();
+            assert_eq!(decrypt(&opening, &ct).unwrap(), b"hello");
+
+            // an opening for another value does not decrypt
+            let ct = encrypt(rng, &ck, &com, z, y + Fr::from(1u64), b"hello");
+            assert_eq!(
+                decrypt(&opening, &ct).err(),
+                Some(LaconicError::AuthenticationFailed)
+            );
+        }
+
+        // the evaluation at a domain point is the committed value
+        let (y, _) = open(&ck, &evals, ck.domain.element(2)).unwrap();
+        assert_eq!(y, evals[2]);
+
+        // with an h1 part, an opening with hat_y = 1 passes the
+        // pairing check, but the pad depends on v only
+        let z = Fr::rand(rng);
+        let (y, mut opening) = open(&ck, &evals, z).unwrap();
+        let com = (com + ck.hat_u[0]).into_affine();
+        opening.hat_y = Fr::one();
+        assert!(plain_kzg_verify(&ck, &com, z, y, &opening));
+        assert!(!verify(&ck, &com, z, y, &opening));
+        let ct = encrypt(rng, &ck, &com, z, y, b"hello");
+        assert_eq!(
+            decrypt(&opening, &ct).err(),
+            Some(LaconicError::InvalidOpening)
+        );
+    }
+}
@@ -1,284 +1,818 @@
-use std::sync::mpsc::Receiver;
-
-use crate::kzg_utils::plain_kzg_com;
-use crate::{kzg_fk_open::all_openings_single, kzg_types::CommitmentKey};
+use crate::error::LaconicError;
+use crate::kzg_bits::{self, BitsProof};
+use crate::kzg_constraints::{self, ConstraintProof, ConstraintSystem, RANDOM_ROWS};
+use crate::kzg_fk_open::all_openings_single;
+use crate::kzg_types::{CommitmentKey, CommitmentKeyRef, MultiOpening, Opening};
+use crate::kzg_utils::{
+    check_indices, plain_kzg_com, plain_kzg_multi_open, plain_kzg_multi_verify,
+    plain_kzg_verify_inside, witness_evals_inside,
+};
+use crate::wire::{check_domain_size, decode, decode_point, encode, CurveId, Kind};
 
 use ark_ec::pairing::{Pairing, PairingOutput};
 use ark_ec::Group;
+use ark_ec::{AffineRepr, CurveGroup};
 use ark_ff::BigInteger;
-use ark_ff::CyclotomicMultSubgroup;
-use ark_ff::PrimeField;
-use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
+use ark_ff::{Field, PrimeField};
+use ark_poly::EvaluationDomain;
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::cfg_iter;
 use ark_std::One;
 use ark_std::UniformRand;
 use ark_std::Zero;
 use rand::Rng;
-use serde::{Deserialize, Serialize};
-use wasm_bindgen::prelude::*;
+#[cfg(feature = "parallel")]
+use rayon::prelude::*;
 
-pub const MSG_SIZE: usize = 16;
+/// size of the authentication tag appended to each ciphertext
+pub const TAG_SIZE: usize = 32;
 
-#[derive(Clone, Copy, Debug)]
+/// OT message for a single index. It holds one witness encryption
+/// per branch, each encapsulating a byte string of arbitrary length.
+/// Each ciphertext carries a tag over the index, the branch and the
+/// receiver commitment, so decrypting with the wrong key is detected
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
 pub struct Msg<E: Pairing> {
-    h: [(E::G2Affine, [u8; MSG_SIZE]); 2],
+    h: [(E::G2Affine, Vec<u8>); 2],
 }
 
 pub struct LaconicOT<E: Pairing, D: EvaluationDomain<E::ScalarField>> {
     ck: CommitmentKey<E, D>,
 }
 
-#[derive(Serialize, Deserialize)]
-struct LaconicOTRecvData {
-    qs: Vec<Vec<u8>>,
-    com: Vec<u8>,
+/// serialized state of the receiver
+#[derive(CanonicalSerialize, CanonicalDeserialize)]
+struct LaconicOTRecvData<E: Pairing> {
     bits: Vec<bool>,
+    padding: Vec<E::ScalarField>,
+    com: E::G1Affine,
+    qs: Vec<E::G1Affine>,
+    revealed: usize,
 }
 
 pub struct LaconicOTRecv<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> {
-    ck: &'a CommitmentKey<E, D>,
+    ck: CommitmentKeyRef<'a, E, D>,
     qs: Vec<E::G1>,
     com: E::G1,
     bits: Vec<bool>,
+
+    /// the random evaluations after the bits,
+    /// needed to open the commitment
+    padding: Vec<E::ScalarField>,
+
+    /// updates (i, delta) that are not yet applied to the
+    /// openings qs[j] for j != i, see update
+    pending: Vec<(usize, E::ScalarField)>,
+
+    /// the number of evaluations outside of the domain
+    /// revealed by proofs so far, see reveal_evaluations
+    revealed: usize,
+}
+
+/// powers base^(2^k) and their inverses, used for fast
+/// exponentiation in the target group with the NAF of a scalar
+type NafTable<E> = Vec<(PairingOutput<E>, PairingOutput<E>)>;
+
+/// Everything the sender precomputes for a fixed receiver commitment.
+/// It can be serialized, so that it only has to be computed once
+/// per commitment, even across restarts
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, PartialEq, Eq, Debug)]
+pub struct SenderPrecomputation<E: Pairing> {
+    /// the receiver commitment for which the tables are computed
+    com: E::G1Affine,
+
+    /// NAF tables for e(com, g2) and e(com - g1, g2)
+    naf: [NafTable<E>; 2],
 }
 
 pub struct LaconicOTSender<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> {
-    ck: &'a CommitmentKey<E, D>,
+    ck: CommitmentKeyRef<'a, E, D>,
     com: E::G1,
+
+    /// only present after calling prepare
+    /// or setting the precomputation
+    precomp: Option<SenderPrecomputation<E>>,
+}
+
+impl<E: Pairing> SenderPrecomputation<E> {
+    pub fn new<D: EvaluationDomain<E::ScalarField>>(ck: &CommitmentKey<E, D>, com: E::G1) -> Self {
+        // y = 0/1
+        let l0 = com;
+        let l1 = com - ck.u[0];
+        let com0 = E::pairing(l0, ck.g2);
+        let com1 = E::pairing(l1, ck.g2);
+        Self {
+            com: com.into(),
+            naf: [naf_table(com0), naf_table(com1)],
+        }
+    }
+
+    /// the receiver commitment for which the tables are computed
+    pub fn commitment(&self) -> E::G1 {
+        self.com.into_group()
+    }
+
+    /// table for send_precompute_naf, for the branch with bit j
+    pub fn naf(&self, j: usize) -> &[(PairingOutput<E>, PairingOutput<E>)] {
+        &self.naf[j]
+    }
+
+    /// table for send_precompute_squares, for the branch with bit j
+    pub fn squares(&self, j: usize) -> Vec<PairingOutput<E>> {
+        self.naf[j].iter().map(|(sq, _)| *sq).collect()
+    }
+}
+
+impl<E: Pairing, D: EvaluationDomain<E::ScalarField>> LaconicOT<E, D> {
+    pub fn new(ck: CommitmentKey<E, D>) -> Self {
+        Self { ck }
+    }
+
+    pub fn ck(&self) -> &CommitmentKey<E, D> {
+        &self.ck
+    }
+
+    /// Commit to the bits with the owned key
+    pub fn receiver(&self, bits: &[bool]) -> Result<LaconicOTRecv<'_, E, D>, LaconicError> {
+        LaconicOTRecv::new(&self.ck, bits)
+    }
+
+    /// Create a sender for the commitment with the owned key
+    pub fn sender(&self, com: E::G1) -> LaconicOTSender<'_, E, D> {
+        LaconicOTSender::new(&self.ck, com)
+    }
 }
 
 impl<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> LaconicOTRecv<'a, E, D> {
-    pub fn new(ck: &'a CommitmentKey<E, D>, bits: &[bool]) -> Self {
-        let mut elems: Vec<_> = bits
-            .iter()
-            .map(|b| {
-                if *b {
-                    E::ScalarField::one()
-                } else {
-                    E::ScalarField::zero()
-                }
-            })
-            .collect();
+    /// Commit to the bits. The key can be borrowed or shared via Arc
+    pub fn new<K: Into<CommitmentKeyRef<'a, E, D>>>(
+        ck: K,
+        bits: &[bool],
+    ) -> Result<Self, LaconicError> {
+        let ck = ck.into();
+        if bits.len() > ck.domain.size() {
+            return Err(LaconicError::MessageTooLong {
+                length: bits.len(),
+                max: ck.domain.size(),
+            });
+        }
+
+        let mut elems: Vec<_> = bits.iter().map(|b| bit_to_scalar::<E>(*b)).collect();
 
         // pad with random elements
-        assert!(elems.len() <= ck.domain.size());
         elems.resize_with(ck.domain.size(), || {
             E::ScalarField::rand(&mut rand::thread_rng())
         });
 
         // compute commitment
-        let com = plain_kzg_com(ck, &elems);
+        let com = plain_kzg_com(&ck, &elems);
 
         // compute all openings
         let qs = all_openings_single::<E, D>(&ck.y, &ck.domain, &elems);
 
-        Self {
+        Ok(Self {
             ck,
             qs,
             com: com.into(),
             bits: bits.to_vec(),
-        }
+            padding: elems[bits.len()..].to_vec(),
+            pending: Vec::new(),
+            revealed: 0,
+        })
     }
 
-    pub fn recv(&self, i: usize, msg: Msg<E>) -> [u8; MSG_SIZE] {
+    pub fn recv(&self, i: usize, msg: &Msg<E>) -> Result<Vec<u8>, LaconicError> {
+        if i >= self.bits.len() {
+            return Err(LaconicError::IndexOutOfRange {
+                index: i,
+                length: self.bits.len(),
+            });
+        }
         let j: usize = if self.bits[i] { 1 } else { 0 };
         let h = msg.h[j].0;
-        let c = msg.h[j].1;
-        let m = E::pairing(self.qs[i], h);
-        decrypt::<E, MSG_SIZE>(m.0, &c)
+        let c = &msg.h[j].1;
+        let m = E::pairing(self.opening(i), h);
+        decrypt::<E>(m.0, &associated_data::<E>(&self.com, i, j), c)
+    }
+
+    /// Change the ith committed bit. The commitment changes by
+    /// (new_bit - old_bit) * lagranges[i] + r * lagranges[k] for a random
+    /// padding index k and random r, so that the sender cannot tell from
+    /// the new commitment which bit changed, or whether any did.
+    /// Fails without padding.
+    /// The openings for i and k are updated with a single MSM each,
+    /// instead of recomputing everything.
+    /// The openings for the other indices are updated lazily
+    /// when they are used, at the cost of two scalar multiplications
+    /// per pending update, see refresh
+    pub fn update(&mut self, i: usize, new_bit: bool) -> Result<(), LaconicError> {
+        if i >= self.bits.len() {
+            return Err(LaconicError::IndexOutOfRange {
+                index: i,
+                length: self.bits.len(),
+            });
+        }
+        if self.padding.is_empty() {
+            return Err(LaconicError::PaddingExhausted {
+                revealed: self.revealed,
+                padding: 0,
+            });
+        }
+        if self.bits[i] != new_bit {
+            let delta = if new_bit {
+                E::ScalarField::one()
+            } else {
+                -E::ScalarField::one()
+            };
+            self.bits[i] = new_bit;
+            self.add(i, delta);
+        }
+
+        // re-randomize the commitment with a padding element,
+        // also if the bit is unchanged so that this is not visible either
+        let rng = &mut rand::thread_rng();
+        let k = rng.gen_range(0..self.padding.len());
+        let r = E::ScalarField::rand(rng);
+        self.padding[k] += r;
+        self.add(self.bits.len() + k, r);
+        Ok(())
+    }
+
+    /// add delta to the ith committed element
+    fn add(&mut self, i: usize, delta: E::ScalarField) {
+        self.com += self.ck.lagranges[i] * delta;
+
+        // the witness polynomial for i changes by
+        // delta * (L_i - 1) / (X - w_i), where L_i is
+        // the ith lagrange polynomial
+        let mut unit = vec![E::ScalarField::zero(); self.ck.domain.size()];
+        unit[i] = E::ScalarField::one();
+        let mut witn_evals = Vec::with_capacity(unit.len());
+        witness_evals_inside::<E, D>(&self.ck.domain, &unit, i, &mut witn_evals);
+        self.qs[i] += plain_kzg_com(&self.ck, &witn_evals) * delta;
+
+        match self.pending.iter_mut().find(|(k, _)| *k == i) {
+            Some((_, d)) => *d += delta,
+            None => self.pending.push((i, delta)),
+        }
+    }
+
+    /// Apply all pending updates to the stored openings.
+    /// This costs two scalar multiplications per index and pending update
+    pub fn refresh(&mut self) {
+        let qs: Vec<_> = (0..self.qs.len()).map(|j| self.opening(j)).collect();
+        self.qs = qs;
+        self.pending.clear();
+    }
+
+    /// the opening for index j with all pending updates applied
+    fn opening(&self, j: usize) -> E::G1 {
+        let mut q = self.qs[j];
+        let n = self.ck.domain.size();
+        let wj = self.ck.domain.element(j);
+        for &(i, delta) in self.pending.iter().filter(|(i, _)| *i != j) {
+            // for j != i, the witness polynomial for j changes by
+            // delta * L_i / (X - w_j), which is
+            // delta * (L_i - w_i / w_j * L_j) / (w_i - w_j)
+            let wi = self.ck.domain.element(i);
+            let factor = delta * (wi - wj).inverse().unwrap();
+            let ratio = self.ck.domain.element((n + i - j) % n);
+            q += self.ck.lagranges[i] * factor - self.ck.lagranges[j] * (factor * ratio);
+        }
+        q
+    }
+
+    /// Publicly reveal the ith bit together with its KZG opening,
+    /// so that it is bound to the same commitment as used for OT
+    pub fn reveal(&self, i: usize) -> Result<(bool, Opening<E>), LaconicError> {
+        if i >= self.bits.len() {
+            return Err(LaconicError::IndexOutOfRange {
+                index: i,
+                length: self.bits.len(),
+            });
+        }
+        let opening = Opening {
+            v: self.opening(i).into_affine(),
+            hat_y: E::ScalarField::zero(),
+        };
+        Ok((self.bits[i], opening))
+    }
+
+    /// Open the committed bits at the given indices with a
+    /// constant-size proof, e.g. for selective disclosure.
+    /// The opened bits are bits()[i] for i in indices
+    pub fn open_multi(&self, indices: &[usize]) -> Result<MultiOpening<E>, LaconicError> {
+        check_indices(indices, self.bits.len())?;
+        Ok(plain_kzg_multi_open(
+            &self.ck,
+            &self.com.into_affine(),
+            &self.evals(),
+            indices,
+        ))
+    }
+
+    /// Prove in zero-knowledge that the commitment holds bits on the
+    /// first bits().len() points of the domain. This needs at least two
+    /// points of padding, i.e., at most domain.size() - 2 bits.
+    /// Every proof reveals another evaluation, so with p points of
+    /// padding at most p - 1 proofs can be computed, see reveal_evaluations
+    pub fn prove_bits<R: Rng>(&mut self, rng: &mut R) -> Result<BitsProof<E>, LaconicError> {
+        let proof = kzg_bits::prove(
+            rng,
+            &self.ck,
+            &self.com.into_affine(),
+            &self.evals(),
+            self.bits.len(),
+        )?;
+        self.reveal_evaluations(kzg_bits::REVEALED_EVALUATIONS)?;
+        Ok(proof)
+    }
+
+    /// Prove in zero-knowledge that the committed bits satisfy cs.
+    /// cs may only refer to committed bits, and there have to be at least
+    /// RANDOM_ROWS points of padding, i.e., at most domain.size() - 3 bits.
+    /// Note that cs has to contain the bits constraint if it relies on it.
+    /// Every proof reveals two evaluations and uses up padding as
+    /// for prove_bits, see reveal_evaluations
+    pub fn prove_constraints<R: Rng>(
+        &mut self,
+        rng: &mut R,
+        cs: &ConstraintSystem<E::ScalarField>,
+    ) -> Result<ConstraintProof<E>, LaconicError> {
+        let max = self.ck.domain.size() - RANDOM_ROWS;
+        if self.bits.len() > max {
+            return Err(LaconicError::MessageTooLong {
+                length: self.bits.len(),
+                max,
+            });
+        }
+        if let Some(row) = cs.max_input_row().filter(|row| *row >= self.bits.len()) {
+            return Err(LaconicError::IndexOutOfRange {
+                index: row,
+                length: self.bits.len(),
+            });
+        }
+        let proof =
+            kzg_constraints::prove(rng, &self.ck, &self.com.into_affine(), &self.evals(), cs)?;
+        self.reveal_evaluations(kzg_constraints::REVEALED_EVALUATIONS)?;
+        Ok(proof)
+    }
+
+    /// Account for count evaluations of the committed polynomial outside
+    /// of the domain revealed by a proof. Together with the bits they
+    /// determine the polynomial once there are as many of them as points
+    /// of padding, so we stop one before, which also leaves one random
+    /// evaluation for the masking of the proof itself.
+    /// Updates do not restore the budget
+    fn reveal_evaluations(&mut self, count: usize) -> Result<(), LaconicError> {
+        if self.revealed + count >= self.padding.len() {
+            return Err(LaconicError::PaddingExhausted {
+                revealed: self.revealed,
+                padding: self.padding.len(),
+            });
+        }
+        self.revealed += count;
+        Ok(())
+    }
+
+    /// the committed evaluations, i.e., the bits followed by the padding
+    fn evals(&self) -> Vec<E::ScalarField> {
+        let mut evals: Vec<_> = self.bits.iter().map(|b| bit_to_scalar::<E>(*b)).collect();
+        evals.extend_from_slice(&self.padding);
+        evals
+    }
+
+    pub fn bits(&self) -> &[bool] {
+        &self.bits
     }
 
     pub fn commitment(&self) -> E::G1 {
         self.com
     }
 
-    pub fn serialize(&self) -> Vec<u8> {
-        let data: LaconicOTRecvData = LaconicOTRecvData {
-            qs: self
-                .qs
-                .iter()
-                .map(|q| {
-                    let mut bytes = Vec::new();
-                    q.serialize_uncompressed(&mut bytes).unwrap();
-                    bytes
-                })
-                .collect(),
-            com: {
-                let mut bytes = Vec::new();
-                self.com.serialize_uncompressed(&mut bytes).unwrap();
-                bytes
-            },
+    /// Encode the commitment in the wire format, to be sent to the sender
+    pub fn commitment_to_bytes(&self) -> Result<Vec<u8>, LaconicError>
+    where
+        E: CurveId,
+    {
+        encode::<E, _>(
+            Kind::Commitment,
+            self.ck.domain.size(),
+            &self.com.into_affine(),
+        )
+    }
+
+    /// Encode the state of the receiver in the wire format
+    pub fn serialize(&self) -> Result<Vec<u8>, LaconicError>
+    where
+        E: CurveId,
+    {
+        let data = LaconicOTRecvData::<E> {
             bits: self.bits.clone(),
+            padding: self.padding.clone(),
+            com: self.com.into_affine(),
+            qs: E::G1::normalize_batch(
+                &(0..self.qs.len())
+                    .map(|j| self.opening(j))
+                    .collect::<Vec<_>>(),
+            ),
+            revealed: self.revealed,
         };
-        serde_json::to_vec(&data).unwrap()
+        encode::<E, _>(Kind::ReceiverState, self.ck.domain.size(), &data)
     }
 
-    pub fn deserialize(data: &[u8], ck: &'a CommitmentKey<E, D>) -> Self {
-        let recv_data: LaconicOTRecvData = serde_json::from_slice(data).unwrap();
-        let qs = recv_data
-            .qs
-            .iter()
-            .map(|q_bytes| {
-                <E as Pairing>::G1::deserialize_uncompressed(q_bytes.as_slice())
-                    .expect("Failed to deserialize G1 point")
-            })
-            .collect();
-
-        let com = <E as Pairing>::G1::deserialize_uncompressed(recv_data.com.as_slice())
-            .expect("Failed to deserialize commitment");
+    pub fn deserialize<K: Into<CommitmentKeyRef<'a, E, D>>>(
+        data: &[u8],
+        ck: K,
+    ) -> Result<Self, LaconicError>
+    where
+        E: CurveId,
+    {
+        let ck = ck.into();
+        let (domain_size, recv_data): (usize, LaconicOTRecvData<E>) =
+            decode::<E, _>(Kind::ReceiverState, data)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        if recv_data.bits.len() > ck.domain.size() {
+            return Err(LaconicError::MessageTooLong {
+                length: recv_data.bits.len(),
+                max: ck.domain.size(),
+            });
+        }
+        // the bits and the padding fill the domain
+        if recv_data.bits.len() + recv_data.padding.len() != ck.domain.size() {
+            return Err(LaconicError::Serialization(format!(
+                "expected {} padding elements, got {}",
+                ck.domain.size() - recv_data.bits.len(),
+                recv_data.padding.len()
+            )));
+        }
+        // there is one opening for every point of the domain
+        if recv_data.qs.len() != ck.domain.size() {
+            return Err(LaconicError::Serialization(format!(
+                "expected {} openings, got {}",
+                ck.domain.size(),
+                recv_data.qs.len()
+            )));
+        }
 
-        LaconicOTRecv {
+        Ok(LaconicOTRecv {
             ck,
-            qs,
-            com,
+            qs: recv_data.qs.iter().map(|q| q.into_group()).collect(),
+            com: recv_data.com.into_group(),
             bits: recv_data.bits,
+            padding: recv_data.padding,
+            pending: Vec::new(),
+            revealed: recv_data.revealed,
+        })
+    }
+}
+
+impl<E: CurveId> Msg<E> {
+    /// Encode the message in the wire format
+    pub fn to_bytes<D: EvaluationDomain<E::ScalarField>>(
+        &self,
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Vec<u8>, LaconicError> {
+        encode::<E, _>(Kind::Message, ck.domain.size(), self)
+    }
+
+    /// Decode a message produced for the same commitment key
+    pub fn from_bytes<D: EvaluationDomain<E::ScalarField>>(
+        bytes: &[u8],
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Self, LaconicError> {
+        let (domain_size, msg) = decode::<E, _>(Kind::Message, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        Ok(msg)
+    }
+
+    /// Encode several messages at once, e.g. all
+    /// messages for the inputs of a garbled circuit
+    pub fn batch_to_bytes<D: EvaluationDomain<E::ScalarField>>(
+        msgs: &[Msg<E>],
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Vec<u8>, LaconicError> {
+        encode::<E, _>(Kind::MessageBatch, ck.domain.size(), &msgs)
+    }
+
+    /// Decode messages encoded with batch_to_bytes
+    pub fn batch_from_bytes<D: EvaluationDomain<E::ScalarField>>(
+        bytes: &[u8],
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Vec<Self>, LaconicError> {
+        let (domain_size, msgs) = decode::<E, _>(Kind::MessageBatch, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        Ok(msgs)
+    }
+}
+
+/// scalar multiplication in the target group using
+/// the non-adjacent form of the scalar and a NAF table
+pub(crate) fn scalar_mul_with_precomputed_naf<E: Pairing>(
+    precomp: &[(PairingOutput<E>, PairingOutput<E>)],
+    scalar: E::ScalarField,
+) -> PairingOutput<E> {
+    let mut result = PairingOutput::<E>::zero();
+
+    for (i, num) in scalar
+        .into_bigint()
+        .find_wnaf(2)
+        .unwrap()
+        .iter()
+        .enumerate()
+    {
+        if *num == 1 {
+            result += precomp[i].0;
+        } else if *num == -1 {
+            result += precomp[i].1;
         }
     }
+    result
 }
 
-fn encrypt<E: Pairing, const N: usize>(pad: E::TargetField, msg: &[u8; N]) -> [u8; N] {
+fn bit_to_scalar<E: Pairing>(b: bool) -> E::ScalarField {
+    if b {
+        E::ScalarField::one()
+    } else {
+        E::ScalarField::zero()
+    }
+}
+
+/// binds a ciphertext to the receiver commitment,
+/// the index i and the branch j of the OT
+pub(crate) fn associated_data<E: Pairing>(com: &E::G1, i: usize, j: usize) -> Vec<u8> {
+    let mut ad = Vec::new();
+    ad.extend_from_slice(&(i as u64).to_le_bytes());
+    ad.push(j as u8);
+    com.serialize_compressed(&mut ad).unwrap();
+    ad
+}
+
+/// derive the MAC key and the key stream from the pad
+fn derive_keys<E: Pairing>(pad: E::TargetField, len: usize) -> ([u8; 32], Vec<u8>) {
     // hash the pad
     let mut hsh = blake3::Hasher::new();
     pad.serialize_uncompressed(&mut hsh).unwrap();
 
-    // xor the message with the pad
+    // the first 32 bytes of output are the MAC key,
+    // the rest is used to mask the message
     let mut xof = hsh.finalize_xof();
-    let mut res = [0u8; N];
-    xof.fill(&mut res);
+    let mut mac_key = [0u8; 32];
+    xof.fill(&mut mac_key);
+    let mut stream = vec![0u8; len];
+    xof.fill(&mut stream);
+    (mac_key, stream)
+}
 
-    for i in 0..N {
-        res[i] ^= msg[i];
+fn tag(mac_key: &[u8; 32], ad: &[u8], ct: &[u8]) -> blake3::Hash {
+    let mut mac = blake3::Hasher::new_keyed(mac_key);
+    mac.update(&(ad.len() as u64).to_le_bytes());
+    mac.update(ad);
+    mac.update(ct);
+    mac.finalize()
+}
+
+/// encrypt-then-MAC: returns the masked message followed by the tag
+pub(crate) fn encrypt<E: Pairing>(pad: E::TargetField, ad: &[u8], msg: &[u8]) -> Vec<u8> {
+    let (mac_key, mut res) = derive_keys::<E>(pad, msg.len());
+
+    // xor the message with the key stream
+    for (r, m) in res.iter_mut().zip(msg) {
+        *r ^= m;
     }
+
+    let t = tag(&mac_key, ad, &res);
+    res.extend_from_slice(t.as_bytes());
     res
 }
 
-fn decrypt<E: Pairing, const N: usize>(pad: E::TargetField, ct: &[u8; N]) -> [u8; N] {
-    encrypt::<E, N>(pad, ct)
+pub(crate) fn decrypt<E: Pairing>(
+    pad: E::TargetField,
+    ad: &[u8],
+    ct: &[u8],
+) -> Result<Vec<u8>, LaconicError> {
+    if ct.len() < TAG_SIZE {
+        return Err(LaconicError::AuthenticationFailed);
+    }
+    let (ct, t) = ct.split_at(ct.len() - TAG_SIZE);
+    let (mac_key, mut res) = derive_keys::<E>(pad, ct.len());
+
+    // blake3::Hash compares in constant time
+    if tag(&mac_key, ad, ct) != blake3::Hash::from_bytes(t.try_into().unwrap()) {
+        return Err(LaconicError::AuthenticationFailed);
+    }
+
+    for (r, c) in res.iter_mut().zip(ct) {
+        *r ^= c;
+    }
+    Ok(res)
 }
 
 impl<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> LaconicOTSender<'a, E, D> {
-    pub fn new(ck: &'a CommitmentKey<E, D>, com: E::G1) -> Self {
-        Self { ck, com }
+    /// Create a sender for the commitment.
+    /// The key can be borrowed or shared via Arc
+    pub fn new<K: Into<CommitmentKeyRef<'a, E, D>>>(ck: K, com: E::G1) -> Self {
+        Self {
+            ck: ck.into(),
+            com,
+            precomp: None,
+        }
+    }
+
+    /// Create a sender for a commitment in the wire format
+    pub fn from_commitment_bytes<K: Into<CommitmentKeyRef<'a, E, D>>>(
+        ck: K,
+        bytes: &[u8],
+    ) -> Result<Self, LaconicError>
+    where
+        E: CurveId,
+    {
+        let ck = ck.into();
+        let (domain_size, com): (usize, E::G1Affine) =
+            decode_point::<E, _>(Kind::Commitment, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        Ok(Self::new(ck, com.into_group()))
+    }
+
+    /// Precompute the pairings e(com, g2) and e(com - g1, g2) along with
+    /// their NAF tables and cache them. Afterwards, send and send_batch
+    /// no longer need to evaluate any pairing
+    pub fn prepare(&mut self) {
+        if self.precomp.is_none() {
+            self.precomp = Some(SenderPrecomputation::new(&self.ck, self.com));
+        }
+    }
+
+    /// the cached tables, if the sender has been prepared
+    pub fn precomputation(&self) -> Option<&SenderPrecomputation<E>> {
+        self.precomp.as_ref()
+    }
+
+    /// Use previously computed (and possibly stored) tables instead of
+    /// calling prepare. Fails if they belong to a different commitment
+    pub fn set_precomputation(
+        &mut self,
+        precomp: SenderPrecomputation<E>,
+    ) -> Result<(), LaconicError> {
+        if precomp.commitment() != self.com {
+            return Err(LaconicError::CommitmentMismatch);
+        }
+        // a shorter table would make the scalar multiplication fail
+        let len = E::ScalarField::MODULUS_BIT_SIZE as usize + 1;
+        if precomp.naf.iter().any(|t| t.len() != len) {
+            return Err(LaconicError::Serialization(format!(
+                "expected NAF tables of length {}",
+                len
+            )));
+        }
+        self.precomp = Some(precomp);
+        Ok(())
+    }
+
+    /// Send the ith pair of messages in msgs for index i.
+    /// Uses the cached tables if the sender has been prepared,
+    /// otherwise the tables are computed once for this batch.
+    /// With the parallel feature, the messages are encapsulated in parallel
+    pub fn send_batch<R: Rng, M: AsRef<[u8]> + Sync>(
+        &self,
+        rng: &mut R,
+        msgs: &[(M, M)],
+    ) -> Result<Vec<Msg<E>>, LaconicError> {
+        if msgs.len() > self.ck.domain.size() {
+            return Err(LaconicError::MessageTooLong {
+                length: msgs.len(),
+                max: self.ck.domain.size(),
+            });
+        }
+
+        let tables;
+        let precomp = match &self.precomp {
+            Some(precomp) => precomp,
+            None => {
+                tables = SenderPrecomputation::new(&self.ck, self.com);
+                &tables
+            }
+        };
+        let [com0_precomp, com1_precomp] = &precomp.naf;
+
+        // sample all randomness upfront, so that
+        // the encapsulation can be done in parallel
+        let rs: Vec<_> = msgs
+            .iter()
+            .map(|_| (E::ScalarField::rand(rng), E::ScalarField::rand(rng)))
+            .collect();
+
+        // the closure must not capture self, which holds the domain
+        let encapsulator = self.encapsulator();
+        let xs: Vec<_> = self.ck.domain.elements().take(msgs.len()).collect();
+
+        let res = cfg_iter!(msgs)
+            .zip(cfg_iter!(rs))
+            .zip(cfg_iter!(xs))
+            .enumerate()
+            .map(|(i, (((m0, m1), (r0, r1)), x))| {
+                let msk0 = scalar_mul_with_precomputed_naf::<E>(com0_precomp, *r0);
+                let msk1 = scalar_mul_with_precomputed_naf::<E>(com1_precomp, *r1);
+                encapsulator.encapsulate(
+                    i,
+                    *x,
+                    [m0.as_ref(), m1.as_ref()],
+                    [*r0, *r1],
+                    [msk0, msk1],
+                )
+            })
+            .collect();
+        Ok(res)
     }
 
     pub fn send_precompute_squares<R: Rng>(
         &self,
         rng: &mut R,
         i: usize,
-        m0: [u8; MSG_SIZE],
-        m1: [u8; MSG_SIZE],
+        m0: &[u8],
+        m1: &[u8],
         com0_squares: &[PairingOutput<E>],
         com1_squares: &[PairingOutput<E>],
-    ) -> Msg<E> {
-        let x = self.ck.domain.element(i);
+    ) -> Result<Msg<E>, LaconicError> {
+        self.element(i)?;
         let r0 = E::ScalarField::rand(rng);
         let r1 = E::ScalarField::rand(rng);
 
-        let g2 = self.ck.g2;
-        let tau = self.ck.r;
-
         // Compute msk0 and msk1 using the precomputed squares
         let msk0 = self.scalar_mul_with_precomputed_squares(com0_squares, r0);
         let msk1 = self.scalar_mul_with_precomputed_squares(com1_squares, r1);
-
-        // h0, h1
-        let g2x = g2 * x;
-        let cm: E::G2 = Into::<E::G2>::into(tau) - g2x;
-        let h0: E::G2 = cm * r0;
-        let h1: E::G2 = cm * r1;
-
-        // encapsulate the messages
-        Msg {
-            h: [
-                (h0.into(), encrypt::<E, MSG_SIZE>(msk0.0, &m0)),
-                (h1.into(), encrypt::<E, MSG_SIZE>(msk1.0, &m1)),
-            ],
-        }
+        Ok(self.encapsulate(i, [m0, m1], [r0, r1], [msk0, msk1]))
     }
 
     pub fn send_precompute_naf<R: Rng>(
         &self,
         rng: &mut R,
         i: usize,
-        m0: [u8; MSG_SIZE],
-        m1: [u8; MSG_SIZE],
+        m0: &[u8],
+        m1: &[u8],
         com0_precomp: &[(PairingOutput<E>, PairingOutput<E>)],
         com1_precomp: &[(PairingOutput<E>, PairingOutput<E>)],
-    ) -> Msg<E> {
-        let x = self.ck.domain.element(i);
+    ) -> Result<Msg<E>, LaconicError> {
+        self.element(i)?;
         let r0 = E::ScalarField::rand(rng);
         let r1 = E::ScalarField::rand(rng);
+        Ok(self.send_with_naf(i, m0, m1, r0, r1, com0_precomp, com1_precomp))
+    }
 
-        let g2 = self.ck.g2;
-        let tau = self.ck.r;
-
-        // Compute msk0 and msk1 using the precomputed squares
-        let msk0 = self.scalar_mul_with_precomputed_naf(com0_precomp, r0);
-        let msk1 = self.scalar_mul_with_precomputed_naf(com1_precomp, r1);
-
-        // h0, h1
-        let g2x = g2 * x;
-        let cm: E::G2 = Into::<E::G2>::into(tau) - g2x;
-        let h0: E::G2 = cm * r0;
-        let h1: E::G2 = cm * r1;
-
-        // encapsulate the messages
-        Msg {
-            h: [
-                (h0.into(), encrypt::<E, MSG_SIZE>(msk0.0, &m0)),
-                (h1.into(), encrypt::<E, MSG_SIZE>(msk1.0, &m1)),
-            ],
-        }
+    #[allow(clippy::too_many_arguments)]
+    fn send_with_naf(
+        &self,
+        i: usize,
+        m0: &[u8],
+        m1: &[u8],
+        r0: E::ScalarField,
+        r1: E::ScalarField,
+        com0_precomp: &[(PairingOutput<E>, PairingOutput<E>)],
+        com1_precomp: &[(PairingOutput<E>, PairingOutput<E>)],
+    ) -> Msg<E> {
+        // Compute msk0 and msk1 using the precomputed NAF tables
+        let msk0 = scalar_mul_with_precomputed_naf::<E>(com0_precomp, r0);
+        let msk1 = scalar_mul_with_precomputed_naf::<E>(com1_precomp, r1);
+        self.encapsulate(i, [m0, m1], [r0, r1], [msk0, msk1])
     }
 
     pub fn send_precompute_pairings<R: Rng>(
         &self,
         rng: &mut R,
         i: usize,
-        m0: [u8; MSG_SIZE],
-        m1: [u8; MSG_SIZE],
+        m0: &[u8],
+        m1: &[u8],
         com0: PairingOutput<E>,
         com1: PairingOutput<E>,
-    ) -> Msg<E> {
-        let x = self.ck.domain.element(i);
+    ) -> Result<Msg<E>, LaconicError> {
+        self.element(i)?;
         let r0 = E::ScalarField::rand(rng);
         let r1 = E::ScalarField::rand(rng);
 
-        let g2 = self.ck.g2;
-        let tau = self.ck.r;
-
         // m0, m1
         let msk0 = com0 * r0;
         let msk1 = com1 * r1;
-
-        // h0, h1
-        let cm = Into::<E::G2>::into(tau) - g2 * x;
-        let h0: E::G2 = cm * r0;
-        let h1: E::G2 = cm * r1;
-
-        // encapsulate the messages
-        Msg {
-            h: [
-                (h0.into(), encrypt::<E, MSG_SIZE>(msk0.0, &m0)),
-                (h1.into(), encrypt::<E, MSG_SIZE>(msk1.0, &m1)),
-            ],
-        }
+        Ok(self.encapsulate(i, [m0, m1], [r0, r1], [msk0, msk1]))
     }
 
     pub fn send<R: Rng>(
         &self,
         rng: &mut R,
         i: usize,
-        m0: [u8; MSG_SIZE],
-        m1: [u8; MSG_SIZE],
-    ) -> Msg<E> {
-        let x = self.ck.domain.element(i);
+        m0: &[u8],
+        m1: &[u8],
+    ) -> Result<Msg<E>, LaconicError> {
+        self.element(i)?;
         let r0 = E::ScalarField::rand(rng);
         let r1 = E::ScalarField::rand(rng);
 
+        // use the cached tables if available
+        if let Some(precomp) = &self.precomp {
+            let [com0_precomp, com1_precomp] = &precomp.naf;
+            return Ok(self.send_with_naf(i, m0, m1, r0, r1, com0_precomp, com1_precomp));
+        }
+
         let g1 = self.ck.u[0];
-        let g2 = self.ck.g2;
-        let tau = self.ck.r;
 
         // y = 0/1
         let l0 = self.com * r0; // r * (c - [y])
@@ -287,19 +821,89 @@ impl<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> LaconicOTSender<'a, E,
         // m0, m1
         let msk0 = E::pairing(l0, self.ck.g2);
         let msk1 = E::pairing(l1, self.ck.g2);
+        Ok(self.encapsulate(i, [m0, m1], [r0, r1], [msk0, msk1]))
+    }
 
-        // h0, h1
-        let cm = Into::<E::G2>::into(tau) - g2 * x;
-        let h0: E::G2 = cm * r0;
-        let h1: E::G2 = cm * r1;
-
-        // encapsulate the messages
-        Msg {
-            h: [
-                (h0.into(), encrypt::<E, MSG_SIZE>(msk0.0, &m0)),
-                (h1.into(), encrypt::<E, MSG_SIZE>(msk1.0, &m1)),
-            ],
+    fn encapsulate(
+        &self,
+        i: usize,
+        ms: [&[u8]; 2],
+        rs: [E::ScalarField; 2],
+        msks: [PairingOutput<E>; 2],
+    ) -> Msg<E> {
+        let x = self.ck.domain.element(i);
+        self.encapsulator().encapsulate(i, x, ms, rs, msks)
+    }
+
+    fn encapsulator(&self) -> Encapsulator<E> {
+        Encapsulator {
+            g2: self.ck.g2,
+            r: self.ck.r,
+            com: self.com,
+        }
+    }
+
+    /// Verify that the receiver committed to bit at index i, see reveal
+    pub fn verify_reveal(&self, i: usize, bit: bool, opening: &Opening<E>) -> bool {
+        // as in verify_multi, a nonzero hat_y would accept
+        // commitments for which OT decrypts to neither message
+        if i >= self.ck.d.len() || !opening.hat_y.is_zero() {
+            return false;
         }
+        plain_kzg_verify_inside(
+            &self.ck,
+            i,
+            &self.com.into_affine(),
+            bit_to_scalar::<E>(bit),
+            opening,
+        )
+    }
+
+    /// Verify that the receiver committed to bits on the first length
+    /// points of the domain. This should be checked before sending
+    /// messages for a receiver that might be malicious
+    pub fn verify_bits(&self, length: usize, proof: &BitsProof<E>) -> bool {
+        kzg_bits::verify(&self.ck, &self.com.into_affine(), length, proof)
+    }
+
+    /// Verify that the committed bits satisfy cs. This should be
+    /// checked before sending messages, e.g. garbled input labels
+    pub fn verify_constraints(
+        &self,
+        cs: &ConstraintSystem<E::ScalarField>,
+        proof: &ConstraintProof<E>,
+    ) -> bool {
+        kzg_constraints::verify(&self.ck, &self.com.into_affine(), cs, proof)
+    }
+
+    /// Verify that the opening proves that the receiver
+    /// committed to bits[k] at index indices[k]
+    pub fn verify_multi(
+        &self,
+        indices: &[usize],
+        bits: &[bool],
+        opening: &MultiOpening<E>,
+    ) -> bool {
+        // OT commitments have no hiding part, with hat_y != 0 the
+        // receiver could open a commitment that also contains h1
+        if !opening.hat_y.is_zero() {
+            return false;
+        }
+        let values: Vec<_> = bits.iter().map(|b| bit_to_scalar::<E>(*b)).collect();
+        plain_kzg_multi_verify(&self.ck, &self.com.into_affine(), indices, &values, opening)
+    }
+
+    /// returns the ith element of the evaluation domain,
+    /// i.e., the point at which the ith bit is committed
+    fn element(&self, i: usize) -> Result<E::ScalarField, LaconicError> {
+        let size = self.ck.domain.size();
+        if i >= size {
+            return Err(LaconicError::IndexOutOfRange {
+                index: i,
+                length: size,
+            });
+        }
+        Ok(self.ck.domain.element(i))
     }
 
     fn scalar_mul_with_precomputed_squares(
@@ -316,34 +920,57 @@ impl<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> LaconicOTSender<'a, E,
         }
         result
     }
+}
+
+/// The parts of the key and the commitment needed to encapsulate.
+/// Unlike the sender, they do not depend on the domain type,
+/// so send_batch can share them between threads
+struct Encapsulator<E: Pairing> {
+    g2: E::G2Affine,
+    r: E::G2Affine,
+    com: E::G1,
+}
 
-    fn scalar_mul_with_precomputed_naf(
+impl<E: Pairing> Encapsulator<E> {
+    /// Encapsulate ms[j] for branch j of index i at the point x, given
+    /// the pad msks[j] = e(com - [j], g2)^rs[j]. All send variants end
+    /// here, so the ciphertexts and their tags are built the same way
+    fn encapsulate(
         &self,
-        precomp: &[(PairingOutput<E>, PairingOutput<E>)],
-        scalar: E::ScalarField,
-    ) -> PairingOutput<E> {
-        let mut result = PairingOutput::<E>::zero();
+        i: usize,
+        x: E::ScalarField,
+        ms: [&[u8]; 2],
+        rs: [E::ScalarField; 2],
+        msks: [PairingOutput<E>; 2],
+    ) -> Msg<E> {
+        // h_j = (tau - x) * r_j in G2
+        let cm = Into::<E::G2>::into(self.r) - self.g2 * x;
+        let h = |j: usize| {
+            let ad = associated_data::<E>(&self.com, i, j);
+            ((cm * rs[j]).into(), encrypt::<E>(msks[j].0, &ad, ms[j]))
+        };
+        Msg { h: [h(0), h(1)] }
+    }
+}
 
-        for (i, num) in scalar
-            .into_bigint()
-            .find_wnaf(2)
-            .unwrap()
-            .iter()
-            .enumerate()
-        {
-            if *num == 1 {
-                result += precomp[i].0;
-            } else if *num == -1 {
-                result += precomp[i].1;
-            }
-        }
-        result
+/// compute the table [(base^(2^k), base^(-2^k))] for all k
+/// up to the length of the NAF of any scalar
+fn naf_table<E: Pairing>(base: PairingOutput<E>) -> NafTable<E> {
+    // the NAF has at most one digit more than the binary representation
+    let len = E::ScalarField::MODULUS_BIT_SIZE as usize + 1;
+    let mut table = Vec::with_capacity(len);
+    let mut cur = base;
+    for _ in 0..len {
+        table.push((cur, -cur));
+        cur.double_in_place();
     }
+    table
 }
 
 #[test]
 fn test_laconic_ot() {
     use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
     use ark_std::test_rng;
 
     let rng = &mut test_rng();
@@ -351,11 +978,11 @@ fn test_laconic_ot() {
     let degree = 4;
     let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, degree).unwrap();
 
-    let receiver = LaconicOTRecv::new(&ck, &[false, true, false, true]);
+    let receiver = LaconicOTRecv::new(&ck, &[false, true, false, true]).unwrap();
     let sender = LaconicOTSender::new(&ck, receiver.commitment());
 
-    let m0 = [0u8; MSG_SIZE];
-    let m1 = [1u8; MSG_SIZE];
+    let m0 = [0u8; 16];
+    let m1 = [1u8; 16];
 
     // precompute pairing
     let l0 = receiver.commitment();
@@ -366,13 +993,15 @@ fn test_laconic_ot() {
     let com1 = Bls12_381::pairing(l1, receiver.ck.g2);
 
     // test normal send
-    let msg = sender.send(rng, 0, m0, m1);
-    let res = receiver.recv(0, msg);
+    let msg = sender.send(rng, 0, &m0, &m1).unwrap();
+    let res = receiver.recv(0, &msg).unwrap();
     assert_eq!(res, m0);
 
     // test without precomputation
-    let msg = sender.send_precompute_pairings(rng, 1, m0, m1, com0, com1);
-    let res = receiver.recv(1, msg);
+    let msg = sender
+        .send_precompute_pairings(rng, 1, &m0, &m1, com0, com1)
+        .unwrap();
+    let res = receiver.recv(1, &msg).unwrap();
     assert_eq!(res, m1);
 
     // precompute naf data
@@ -392,19 +1021,40 @@ fn test_laconic_ot() {
     }
 
     // test with precompute squares
-    let msg = sender.send_precompute_squares(rng, 2, m0, m1, &com0_squares, &com1_squares);
-    let res = receiver.recv(2, msg);
+    let msg = sender
+        .send_precompute_squares(rng, 2, &m0, &m1, &com0_squares, &com1_squares)
+        .unwrap();
+    let res = receiver.recv(2, &msg).unwrap();
     assert_eq!(res, m0);
 
     // test with precompute naf
-    let msg = sender.send_precompute_naf(rng, 3, m0, m1, &com0_precomp, &com1_precomp);
-    let res = receiver.recv(3, msg);
+    let msg = sender
+        .send_precompute_naf(rng, 3, &m0, &m1, &com0_precomp, &com1_precomp)
+        .unwrap();
+    let res = receiver.recv(3, &msg).unwrap();
     assert_eq!(res, m1);
 }
 
+#[test]
+fn test_owned_key() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 4).unwrap();
+    let ot = LaconicOT::new(ck);
+
+    let receiver = ot.receiver(&[true, false]).unwrap();
+    let sender = ot.sender(receiver.commitment());
+    let msg = sender.send(rng, 0, &[0u8; 16], &[1u8; 16]).unwrap();
+    assert_eq!(receiver.recv(0, &msg).unwrap(), vec![1u8; 16]);
+}
+
 #[test]
 fn test_serialize_deserialize() {
     use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
     use ark_std::test_rng;
 
     let rng = &mut test_rng();
@@ -412,16 +1062,718 @@ fn test_serialize_deserialize() {
     let degree = 4;
     let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, degree).unwrap();
 
-    let receiver = LaconicOTRecv::new(&ck, &[false, true, false, true]);
+    let receiver = LaconicOTRecv::new(&ck, &[false, true, false, true]).unwrap();
 
     // Serialize the receiver
-    let serialized = receiver.serialize();
+    let serialized = receiver.serialize().unwrap();
 
     // Deserialize the receiver
-    let deserialized_receiver = LaconicOTRecv::deserialize(&serialized, &ck);
+    let deserialized_receiver = LaconicOTRecv::deserialize(&serialized, &ck).unwrap();
 
     // Check that the deserialized receiver matches the original
     assert_eq!(receiver.bits, deserialized_receiver.bits);
     assert_eq!(receiver.com, deserialized_receiver.com);
     assert_eq!(receiver.qs, deserialized_receiver.qs);
+
+    // the commitment and messages also survive the wire format
+    let bytes = receiver.commitment_to_bytes().unwrap();
+    let sender = LaconicOTSender::from_commitment_bytes(&ck, &bytes).unwrap();
+    let msg = sender.send(rng, 1, &[0u8; 16], &[1u8; 16]).unwrap();
+    let bytes = msg.to_bytes(&ck).unwrap();
+    let msg = Msg::from_bytes(&bytes, &ck).unwrap();
+    assert_eq!(deserialized_receiver.recv(1, &msg).unwrap(), vec![1u8; 16]);
+
+    let msgs = sender
+        .send_batch(rng, &[([0u8; 16], [1u8; 16]), ([2u8; 16], [3u8; 16])])
+        .unwrap();
+    let bytes = Msg::batch_to_bytes(&msgs, &ck).unwrap();
+    assert!(matches!(
+        Msg::from_bytes(&bytes, &ck),
+        Err(LaconicError::Serialization(_))
+    ));
+    let msgs = Msg::batch_from_bytes(&bytes, &ck).unwrap();
+    assert_eq!(msgs.len(), 2);
+    assert_eq!(
+        deserialized_receiver.recv(1, &msgs[1]).unwrap(),
+        vec![3u8; 16]
+    );
+}
+
+#[test]
+fn test_reject_bad_input() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    // empty messages are not supported
+    let res = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 0);
+    assert_eq!(
+        res.err(),
+        Some(LaconicError::DomainTooSmall { message_length: 0 })
+    );
+
+    let degree = 4;
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, degree).unwrap();
+
+    // more bits than the domain can hold
+    let res = LaconicOTRecv::new(&ck, &[true; 5]);
+    assert_eq!(
+        res.err(),
+        Some(LaconicError::MessageTooLong { length: 5, max: 4 })
+    );
+
+    let receiver = LaconicOTRecv::new(&ck, &[false, true]).unwrap();
+    let sender = LaconicOTSender::new(&ck, receiver.commitment());
+
+    // index outside of the domain
+    let res = sender.send(rng, 4, &[0u8; 16], &[1u8; 16]);
+    assert_eq!(
+        res.err(),
+        Some(LaconicError::IndexOutOfRange {
+            index: 4,
+            length: 4
+        })
+    );
+
+    // index outside of the committed bits
+    let msg = sender.send(rng, 2, &[0u8; 16], &[1u8; 16]).unwrap();
+    assert_eq!(
+        receiver.recv(2, &msg).err(),
+        Some(LaconicError::IndexOutOfRange {
+            index: 2,
+            length: 2
+        })
+    );
+
+    // garbage receiver state
+    let res = LaconicOTRecv::deserialize(b"not a receiver", &ck);
+    assert!(matches!(res.err(), Some(LaconicError::Serialization(_))));
+
+    // corrupted commitment, it directly follows the bits and the padding
+    let mut data = receiver.serialize().unwrap();
+    let offset = crate::wire::HEADER_SIZE + 8 + 2 + 8 + 2 * 32;
+    data[offset + 10] ^= 1;
+    let res = LaconicOTRecv::deserialize(&data, &ck);
+    assert!(matches!(res.err(), Some(LaconicError::Serialization(_))));
+
+    // x coordinate larger than the modulus, keeping the flag bits
+    let mut data = receiver.commitment_to_bytes().unwrap();
+    data[crate::wire::HEADER_SIZE] |= 0x1f;
+    data[crate::wire::HEADER_SIZE + 1..].fill(0xff);
+    let res = LaconicOTSender::from_commitment_bytes(&ck, &data);
+    assert_eq!(res.err(), Some(LaconicError::MalformedPoint));
+
+    // state and commitment for a key with a different domain
+    let other_ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 8).unwrap();
+    let data = receiver.serialize().unwrap();
+    let res = LaconicOTRecv::deserialize(&data, &other_ck);
+    assert_eq!(
+        res.err(),
+        Some(LaconicError::DomainMismatch {
+            expected: 8,
+            found: 4
+        })
+    );
+    let data = receiver.commitment_to_bytes().unwrap();
+    let res = LaconicOTSender::from_commitment_bytes(&other_ck, &data);
+    assert!(matches!(
+        res.err(),
+        Some(LaconicError::DomainMismatch { .. })
+    ));
+}
+
+#[test]
+fn test_variable_length_messages() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let degree = 4;
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, degree).unwrap();
+
+    let receiver = LaconicOTRecv::new(&ck, &[false, true, false, true]).unwrap();
+    let sender = LaconicOTSender::new(&ck, receiver.commitment());
+
+    // labels, free-XOR offsets, key blobs, ...
+    let lengths = [0, 1, 32, 1000];
+    for (i, len) in lengths.iter().enumerate() {
+        let m0: Vec<u8> = (0..*len).map(|_| rng.gen()).collect();
+        let m1: Vec<u8> = (0..*len).map(|_| rng.gen()).collect();
+        let msg = sender.send(rng, i, &m0, &m1).unwrap();
+        let res = receiver.recv(i, &msg).unwrap();
+        assert_eq!(res, if i % 2 == 0 { m0 } else { m1 });
+    }
+
+    // the two branches may have different lengths
+    let msg = sender.send(rng, 1, &[1u8; 16], &[2u8; 48]).unwrap();
+    assert_eq!(receiver.recv(1, &msg).unwrap(), vec![2u8; 48]);
+}
+
+#[test]
+fn test_detect_tampering() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let degree = 4;
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, degree).unwrap();
+
+    let receiver = LaconicOTRecv::new(&ck, &[false, true, false, true]).unwrap();
+    let sender = LaconicOTSender::new(&ck, receiver.commitment());
+
+    let m0 = [0u8; 16];
+    let m1 = [1u8; 16];
+    let msg = sender.send(rng, 1, &m0, &m1).unwrap();
+    assert_eq!(receiver.recv(1, &msg).unwrap(), m1);
+
+    // flipped ciphertext bit
+    let mut tampered = msg.clone();
+    tampered.h[1].1[0] ^= 1;
+    assert_eq!(
+        receiver.recv(1, &tampered).err(),
+        Some(LaconicError::AuthenticationFailed)
+    );
+
+    // flipped tag bit
+    let mut tampered = msg.clone();
+    tampered.h[1].1[16] ^= 1;
+    assert_eq!(
+        receiver.recv(1, &tampered).err(),
+        Some(LaconicError::AuthenticationFailed)
+    );
+
+    // truncated ciphertext
+    let mut tampered = msg.clone();
+    tampered.h[1].1.truncate(TAG_SIZE - 1);
+    assert_eq!(
+        receiver.recv(1, &tampered).err(),
+        Some(LaconicError::AuthenticationFailed)
+    );
+
+    // swapped branches
+    let mut tampered = msg.clone();
+    tampered.h.swap(0, 1);
+    assert_eq!(
+        receiver.recv(1, &tampered).err(),
+        Some(LaconicError::AuthenticationFailed)
+    );
+
+    // message for a different index
+    assert_eq!(
+        receiver.recv(3, &msg).err(),
+        Some(LaconicError::AuthenticationFailed)
+    );
+
+    // message for a different commitment with the same bit at index 1
+    let other = LaconicOTRecv::new(&ck, &[true, true, false, false]).unwrap();
+    assert_eq!(
+        other.recv(1, &msg).err(),
+        Some(LaconicError::AuthenticationFailed)
+    );
+}
+
+#[test]
+fn test_send_batch() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let degree = 8;
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, degree).unwrap();
+
+    let bits: Vec<bool> = (0..degree).map(|_| rng.gen_bool(0.5)).collect();
+    let receiver = LaconicOTRecv::new(&ck, &bits).unwrap();
+    let mut sender = LaconicOTSender::new(&ck, receiver.commitment());
+
+    let labels: Vec<(Vec<u8>, Vec<u8>)> = (0..degree)
+        .map(|_| {
+            let m0: Vec<u8> = (0..16).map(|_| rng.gen()).collect();
+            let m1: Vec<u8> = (0..16).map(|_| rng.gen()).collect();
+            (m0, m1)
+        })
+        .collect();
+
+    let check = |msgs: &[Msg<Bls12_381>]| {
+        assert_eq!(msgs.len(), degree);
+        for (i, msg) in msgs.iter().enumerate() {
+            let res = receiver.recv(i, msg).unwrap();
+            let expected = if bits[i] { &labels[i].1 } else { &labels[i].0 };
+            assert_eq!(&res, expected);
+        }
+    };
+
+    // without cached tables
+    check(&sender.send_batch(rng, &labels).unwrap());
+
+    // with cached tables
+    sender.prepare();
+    check(&sender.send_batch(rng, &labels).unwrap());
+
+    // single sends use the cached tables too
+    let msg = sender.send(rng, 0, &labels[0].0, &labels[0].1).unwrap();
+    let expected = if bits[0] { &labels[0].1 } else { &labels[0].0 };
+    assert_eq!(&receiver.recv(0, &msg).unwrap(), expected);
+
+    // more messages than the domain can hold
+    let too_many = vec![([0u8; 16], [1u8; 16]); degree + 1];
+    assert_eq!(
+        sender.send_batch(rng, &too_many).err(),
+        Some(LaconicError::MessageTooLong {
+            length: degree + 1,
+            max: degree
+        })
+    );
+}
+
+#[test]
+fn test_sender_precomputation() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let degree = 4;
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, degree).unwrap();
+
+    let receiver = LaconicOTRecv::new(&ck, &[false, true, false, true]).unwrap();
+    let mut sender = LaconicOTSender::new(&ck, receiver.commitment());
+    assert!(sender.precomputation().is_none());
+    sender.prepare();
+
+    // store the tables and load them again
+    let mut bytes = Vec::new();
+    sender
+        .precomputation()
+        .unwrap()
+        .serialize_compressed(&mut bytes)
+        .unwrap();
+    let precomp = SenderPrecomputation::<Bls12_381>::deserialize_compressed(&bytes[..]).unwrap();
+    assert_eq!(&precomp, sender.precomputation().unwrap());
+
+    // a tampered table entry is not in the target group,
+    // the entries start after the commitment and the length
+    let mut bad = bytes.clone();
+    bad[ck.u[0].compressed_size() + 8] ^= 1;
+    assert!(SenderPrecomputation::<Bls12_381>::deserialize_compressed_unchecked(&bad[..]).is_ok());
+    assert!(SenderPrecomputation::<Bls12_381>::deserialize_compressed(&bad[..]).is_err());
+
+    // a fresh sender can use the loaded tables
+    let mut sender = LaconicOTSender::new(&ck, receiver.commitment());
+    sender.set_precomputation(precomp.clone()).unwrap();
+    let msgs = sender
+        .send_batch(rng, &[([0u8; 16], [1u8; 16]); 4])
+        .unwrap();
+    for (i, msg) in msgs.iter().enumerate() {
+        assert_eq!(receiver.recv(i, msg).unwrap(), [(i % 2) as u8; 16]);
+    }
+
+    // the low level interface accepts the tables as well
+    let msg = sender
+        .send_precompute_naf(
+            rng,
+            1,
+            &[0u8; 16],
+            &[1u8; 16],
+            precomp.naf(0),
+            precomp.naf(1),
+        )
+        .unwrap();
+    assert_eq!(receiver.recv(1, &msg).unwrap(), [1u8; 16]);
+    let msg = sender
+        .send_precompute_squares(
+            rng,
+            2,
+            &[0u8; 16],
+            &[1u8; 16],
+            &precomp.squares(0),
+            &precomp.squares(1),
+        )
+        .unwrap();
+    assert_eq!(receiver.recv(2, &msg).unwrap(), [0u8; 16]);
+
+    // tables for another commitment are rejected
+    let other = LaconicOTRecv::new(&ck, &[true, true, false, false]).unwrap();
+    let mut sender = LaconicOTSender::new(&ck, other.commitment());
+    assert_eq!(
+        sender.set_precomputation(precomp),
+        Err(LaconicError::CommitmentMismatch)
+    );
+}
+
+#[test]
+fn test_shared_commitment_key() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+    use std::sync::Arc;
+
+    let rng = &mut test_rng();
+
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 4).unwrap();
+    let ck = Arc::new(ck);
+
+    // the parties do not borrow the key, so they can outlive this scope
+    let (receiver, sender) = {
+        let receiver = LaconicOTRecv::new(ck.clone(), &[true, false]).unwrap();
+        let sender = LaconicOTSender::new(ck.clone(), receiver.commitment());
+        (receiver, sender)
+    };
+    assert_eq!(Arc::strong_count(&ck), 3);
+
+    let msg = sender.send(rng, 0, &[0u8; 16], &[1u8; 16]).unwrap();
+    assert_eq!(receiver.recv(0, &msg).unwrap(), vec![1u8; 16]);
+
+    // dropping the parties releases the key
+    drop(receiver);
+    drop(sender);
+    assert_eq!(Arc::strong_count(&ck), 1);
+}
+
+#[cfg(test)]
+fn round_trip<E: CurveId>() {
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    type D<E> = Radix2EvaluationDomain<<E as Pairing>::ScalarField>;
+
+    let ck = CommitmentKey::<E, D<E>>::setup(rng, 4).unwrap();
+    let ck_bytes = ck.to_bytes().unwrap();
+    let ck = CommitmentKey::<E, D<E>>::from_bytes(&ck_bytes).unwrap();
+    assert_eq!(ck.verify_batched(rng), Ok(()));
+
+    let bits = [true, false, false, true];
+    let receiver = LaconicOTRecv::new(&ck, &bits).unwrap();
+    let state = receiver.serialize().unwrap();
+    let receiver = LaconicOTRecv::deserialize(&state, &ck).unwrap();
+
+    let com = receiver.commitment_to_bytes().unwrap();
+    let mut sender = LaconicOTSender::from_commitment_bytes(&ck, &com).unwrap();
+    sender.prepare();
+    let msgs = sender
+        .send_batch(rng, &[([0u8; 16], [1u8; 16]); 4])
+        .unwrap();
+    let msgs = Msg::batch_from_bytes(&Msg::batch_to_bytes(&msgs, &ck).unwrap(), &ck).unwrap();
+
+    for (i, bit) in bits.iter().enumerate() {
+        let res = receiver.recv(i, &msgs[i]).unwrap();
+        assert_eq!(res, vec![*bit as u8; 16]);
+    }
+}
+
+#[test]
+fn test_curves() {
+    use ark_bls12_377::Bls12_377;
+    use ark_bls12_381::Bls12_381;
+    use ark_bn254::Bn254;
+
+    round_trip::<Bls12_381>();
+    round_trip::<Bn254>();
+    round_trip::<Bls12_377>();
+
+    // objects for one curve are rejected for another
+    let rng = &mut ark_std::test_rng();
+    let ck = CommitmentKey::<Bn254, ark_poly::Radix2EvaluationDomain<ark_bn254::Fr>>::setup(rng, 4)
+        .unwrap();
+    let bytes = ck.to_bytes().unwrap();
+    let res =
+        CommitmentKey::<Bls12_377, ark_poly::Radix2EvaluationDomain<ark_bls12_377::Fr>>::from_bytes(
+            &bytes,
+        );
+    assert_eq!(
+        res.err(),
+        Some(LaconicError::CurveMismatch {
+            expected: 3,
+            found: 2
+        })
+    );
+}
+
+#[test]
+fn test_update() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 8).unwrap();
+
+    let mut bits = vec![true, false, false, true, true, false];
+    let mut receiver = LaconicOTRecv::new(&ck, &bits).unwrap();
+
+    // flip a few bits, one of them twice, and set one to its current value
+    for (i, bit) in [(1, true), (3, false), (1, false), (5, true), (5, true)] {
+        let com = receiver.commitment();
+        receiver.update(i, bit).unwrap();
+        bits[i] = bit;
+
+        // the change of the commitment reveals neither i nor whether
+        // the bit changed at all
+        let diff = receiver.commitment() - com;
+        assert!(!diff.is_zero());
+        assert_ne!(diff, ck.lagranges[i].into_group());
+        assert_ne!(diff, -ck.lagranges[i].into_group());
+    }
+    assert_eq!(receiver.bits, bits);
+
+    // the padding is consistent with the commitment
+    let mut elems: Vec<_> = bits
+        .iter()
+        .map(|b| bit_to_scalar::<Bls12_381>(*b))
+        .collect();
+    elems.extend_from_slice(&receiver.padding);
+    assert_eq!(plain_kzg_com(&ck, &elems), receiver.commitment());
+
+    let check = |receiver: &LaconicOTRecv<Bls12_381, Radix2EvaluationDomain<Fr>>| {
+        let rng = &mut rand::thread_rng();
+        let sender = LaconicOTSender::new(&ck, receiver.commitment());
+        for (i, bit) in bits.iter().enumerate() {
+            let msg = sender.send(rng, i, &[0u8; 16], &[1u8; 16]).unwrap();
+            assert_eq!(receiver.recv(i, &msg).unwrap(), vec![*bit as u8; 16]);
+        }
+    };
+
+    // with pending updates, after serialization and after refreshing
+    check(&receiver);
+    let serialized = receiver.serialize().unwrap();
+    check(&LaconicOTRecv::deserialize(&serialized, &ck).unwrap());
+    receiver.refresh();
+    check(&receiver);
+
+    assert_eq!(
+        receiver.update(6, true).err(),
+        Some(LaconicError::IndexOutOfRange {
+            index: 6,
+            length: 6
+        })
+    );
+
+    // without padding the update would reveal the bit
+    let mut receiver = LaconicOTRecv::new(&ck, &[true; 8]).unwrap();
+    assert_eq!(
+        receiver.update(0, false).err(),
+        Some(LaconicError::PaddingExhausted {
+            revealed: 0,
+            padding: 0
+        })
+    );
+}
+
+#[test]
+fn test_open_multi() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 8).unwrap();
+
+    let mut receiver = LaconicOTRecv::new(&ck, &[true, false, false, true, true, false]).unwrap();
+    receiver.update(2, true).unwrap();
+    let sender = LaconicOTSender::new(&ck, receiver.commitment());
+
+    // disclose the bits at 0, 2 and 5 only
+    let indices = [0, 2, 5];
+    let opening = receiver.open_multi(&indices).unwrap();
+    assert!(sender.verify_multi(&indices, &[true, true, false], &opening));
+    assert!(!sender.verify_multi(&indices, &[true, false, false], &opening));
+    assert!(!sender.verify_multi(&[0, 1, 5], &[true, true, false], &opening));
+
+    // the padding is part of the serialized state
+    let receiver = LaconicOTRecv::deserialize(&receiver.serialize().unwrap(), &ck).unwrap();
+    let opening = receiver.open_multi(&[4, 1]).unwrap();
+    assert!(sender.verify_multi(&[4, 1], &[true, false], &opening));
+
+    // a commitment with an h1 part could be opened with hat_y != 0,
+    // but then the receiver cannot decrypt any OT message for it
+    let com = receiver.commitment() + ck.hat_u[0];
+    let sender = LaconicOTSender::new(&ck, com);
+    let mut evals: Vec<_> = receiver
+        .bits
+        .iter()
+        .map(|b| bit_to_scalar::<Bls12_381>(*b))
+        .collect();
+    evals.extend_from_slice(&receiver.padding);
+    let mut opening = plain_kzg_multi_open(&ck, &com.into_affine(), &evals, &[4, 1]);
+    opening.hat_y = Fr::one();
+    assert!(!sender.verify_multi(&[4, 1], &[true, false], &opening));
+
+    // only committed bits can be opened
+    assert_eq!(
+        receiver.open_multi(&[6]).err(),
+        Some(LaconicError::IndexOutOfRange {
+            index: 6,
+            length: 6
+        })
+    );
+}
+
+#[test]
+fn test_reveal() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 4).unwrap();
+
+    let mut receiver = LaconicOTRecv::new(&ck, &[true, false, true]).unwrap();
+    receiver.update(1, true).unwrap();
+    let sender = LaconicOTSender::new(&ck, receiver.commitment());
+
+    // e.g. the recruiter bit at offset 0, and a bit with a pending update
+    for i in [0, 1] {
+        let (bit, opening) = receiver.reveal(i).unwrap();
+        assert!(bit);
+        assert!(sender.verify_reveal(i, bit, &opening));
+        assert!(!sender.verify_reveal(i, !bit, &opening));
+        assert!(!sender.verify_reveal(1 - i, bit, &opening));
+        assert!(!sender.verify_reveal(4, bit, &opening));
+    }
+
+    assert_eq!(
+        receiver.reveal(3).err(),
+        Some(LaconicError::IndexOutOfRange {
+            index: 3,
+            length: 3
+        })
+    );
+}
+
+#[test]
+fn test_reveal_hiding_part() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+
+    type D = Radix2EvaluationDomain<Fr>;
+
+    let rng = &mut rand::thread_rng();
+
+    let mut ceremony = crate::Ceremony::<Bls12_381>::new::<D>(4).unwrap();
+    ceremony.contribute(rng);
+    let ck = ceremony.commitment_key::<D, _>(rng).unwrap();
+
+    // the receiver adds h1 to its commitment, so that neither OT
+    // message can be decrypted, and opens it with hat_y = 1
+    let receiver = LaconicOTRecv::new(&ck, &[true, false, true]).unwrap();
+    let com = receiver.commitment() + ck.hat_u[0];
+    let sender = LaconicOTSender::new(&ck, com);
+    let msg = sender.send(rng, 0, &[0u8; 16], &[1u8; 16]).unwrap();
+    assert_eq!(
+        receiver.recv(0, &msg).err(),
+        Some(LaconicError::AuthenticationFailed)
+    );
+
+    let (bit, opening) = receiver.reveal(0).unwrap();
+    let forged = Opening {
+        v: opening.v,
+        hat_y: Fr::one(),
+    };
+    assert!(!sender.verify_reveal(0, bit, &forged));
+}
+
+#[test]
+fn test_prove_bits() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 8).unwrap();
+
+    let mut receiver = LaconicOTRecv::new(&ck, &[true, false, false, true, true, false]).unwrap();
+    receiver.update(1, true).unwrap();
+    let sender = LaconicOTSender::new(&ck, receiver.commitment());
+
+    let proof = receiver.prove_bits(rng).unwrap();
+    assert!(sender.verify_bits(6, &proof));
+    assert!(!sender.verify_bits(7, &proof));
+
+    // two points of padding only hide a single proof,
+    // also after serializing the receiver
+    let mut receiver = LaconicOTRecv::deserialize(&receiver.serialize().unwrap(), &ck).unwrap();
+    assert_eq!(
+        receiver.prove_bits(rng).err(),
+        Some(LaconicError::PaddingExhausted {
+            revealed: 1,
+            padding: 2
+        })
+    );
+
+    // without padding there is no zero-knowledge
+    let mut receiver = LaconicOTRecv::new(&ck, &[true; 8]).unwrap();
+    assert_eq!(
+        receiver.prove_bits(rng).err(),
+        Some(LaconicError::MessageTooLong { length: 8, max: 6 })
+    );
+}
+
+#[test]
+fn test_prove_constraints() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 16).unwrap();
+
+    // a candidate with 2 of 4 in education and a salary of 0b0110 = 6
+    let bits = [false, true, true, false, false, false, true, true, false];
+    let mut receiver = LaconicOTRecv::new(&ck, &bits).unwrap();
+    let sender = LaconicOTSender::new(&ck, receiver.commitment());
+
+    let mut cs = ConstraintSystem::new();
+    cs.bits(0, 9)
+        .fixed(0, false)
+        .prefix_ones(1, 4)
+        .at_least(5, 4, 5)
+        .at_most(5, 4, 9);
+    let proof = receiver.prove_constraints(rng, &cs).unwrap();
+    assert!(sender.verify_constraints(&cs, &proof));
+
+    // each proof reveals two evaluations, so seven points of
+    // padding hide three proofs, also together with bit proofs
+    receiver.prove_constraints(rng, &cs).unwrap();
+    receiver.prove_bits(rng).unwrap();
+    assert_eq!(
+        receiver.prove_constraints(rng, &cs).err(),
+        Some(LaconicError::PaddingExhausted {
+            revealed: 5,
+            padding: 7
+        })
+    );
+    receiver.prove_bits(rng).unwrap();
+    assert_eq!(
+        receiver.prove_bits(rng).err(),
+        Some(LaconicError::PaddingExhausted {
+            revealed: 6,
+            padding: 7
+        })
+    );
+
+    // the statement may only refer to committed bits
+    let mut cs = ConstraintSystem::new();
+    cs.prefix_ones(6, 4);
+    assert_eq!(
+        receiver.prove_constraints(rng, &cs).err(),
+        Some(LaconicError::IndexOutOfRange {
+            index: 9,
+            length: 9
+        })
+    );
 }
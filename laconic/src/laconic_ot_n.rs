@@ -0,0 +1,290 @@
+use crate::error::LaconicError;
+use crate::kzg_fk_open::all_openings_single;
+use crate::kzg_types::{CommitmentKey, CommitmentKeyRef};
+use crate::kzg_utils::plain_kzg_com;
+use crate::laconic_ot::{
+    associated_data, decrypt, encrypt, scalar_mul_with_precomputed_naf, SenderPrecomputation,
+};
+use crate::wire::{check_domain_size, decode, encode, CurveId, Kind};
+
+use ark_ec::pairing::{Pairing, PairingOutput};
+use ark_ec::CurveGroup;
+use ark_poly::EvaluationDomain;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use ark_std::One;
+use ark_std::UniformRand;
+use rand::Rng;
+
+// 1-out-of-N laconic OT. The receiver commits to values in 0..N
+// instead of bits, and the sender encrypts one message per possible
+// value towards (com, z_i, y) for every y in 0..N.
+// The pairing for value y is derived from the two pairings used for
+// bits, since e(com - y * g1, g2) = (1 - y) * e(com, g2) + y * e(com - g1, g2),
+// so the sender can reuse the SenderPrecomputation of the binary OT.
+
+/// largest number of values per index,
+/// so that the branch fits into the associated data
+pub const MAX_VALUES: usize = 256;
+
+/// OT message for a single index. It holds
+/// one witness encryption per possible value
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct MsgN<E: Pairing> {
+    h: Vec<(E::G2Affine, Vec<u8>)>,
+}
+
+pub struct LaconicOTNRecv<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> {
+    ck: CommitmentKeyRef<'a, E, D>,
+    qs: Vec<E::G1>,
+    com: E::G1,
+    n: usize,
+    values: Vec<u8>,
+}
+
+pub struct LaconicOTNSender<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> {
+    ck: CommitmentKeyRef<'a, E, D>,
+    com: E::G1,
+
+    /// only present after calling prepare
+    precomp: Option<SenderPrecomputation<E>>,
+}
+
+/// checks that 2 <= n <= MAX_VALUES
+fn check_n(n: usize) -> Result<(), LaconicError> {
+    if !(2..=MAX_VALUES).contains(&n) {
+        return Err(LaconicError::InvalidValueCount { n });
+    }
+    Ok(())
+}
+
+impl<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> LaconicOTNRecv<'a, E, D> {
+    /// Commit to values in 0..n, one per index
+    pub fn new<K: Into<CommitmentKeyRef<'a, E, D>>>(
+        ck: K,
+        n: usize,
+        values: &[u8],
+    ) -> Result<Self, LaconicError> {
+        let ck = ck.into();
+        check_n(n)?;
+        if values.len() > ck.domain.size() {
+            return Err(LaconicError::MessageTooLong {
+                length: values.len(),
+                max: ck.domain.size(),
+            });
+        }
+        if let Some(&v) = values.iter().find(|v| **v as usize >= n) {
+            return Err(LaconicError::ValueOutOfRange { value: v, n });
+        }
+
+        let mut elems: Vec<_> = values.iter().map(|v| E::ScalarField::from(*v)).collect();
+
+        // pad with random elements
+        elems.resize_with(ck.domain.size(), || {
+            E::ScalarField::rand(&mut rand::thread_rng())
+        });
+
+        // compute commitment
+        let com = plain_kzg_com(&ck, &elems);
+
+        // compute all openings
+        let qs = all_openings_single::<E, D>(&ck.y, &ck.domain, &elems);
+
+        Ok(Self {
+            ck,
+            qs,
+            com: com.into(),
+            n,
+            values: values.to_vec(),
+        })
+    }
+
+    /// Decrypt the message for the committed value at index i
+    pub fn recv(&self, i: usize, msg: &MsgN<E>) -> Result<Vec<u8>, LaconicError> {
+        if i >= self.values.len() {
+            return Err(LaconicError::IndexOutOfRange {
+                index: i,
+                length: self.values.len(),
+            });
+        }
+        if msg.h.len() != self.n {
+            return Err(LaconicError::InvalidValueCount { n: msg.h.len() });
+        }
+        let j = self.values[i] as usize;
+        let (h, c) = &msg.h[j];
+        let m = E::pairing(self.qs[i], h);
+        decrypt::<E>(m.0, &associated_data::<E>(&self.com, i, j), c)
+    }
+
+    pub fn commitment(&self) -> E::G1 {
+        self.com
+    }
+
+    /// Encode the commitment in the wire format, to be sent to the sender
+    pub fn commitment_to_bytes(&self) -> Result<Vec<u8>, LaconicError>
+    where
+        E: CurveId,
+    {
+        encode::<E, _>(
+            Kind::Commitment,
+            self.ck.domain.size(),
+            &self.com.into_affine(),
+        )
+    }
+}
+
+impl<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> LaconicOTNSender<'a, E, D> {
+    pub fn new<K: Into<CommitmentKeyRef<'a, E, D>>>(ck: K, com: E::G1) -> Self {
+        Self {
+            ck: ck.into(),
+            com,
+            precomp: None,
+        }
+    }
+
+    /// Precompute the pairings and their NAF tables, see LaconicOTSender::prepare
+    pub fn prepare(&mut self) {
+        if self.precomp.is_none() {
+            self.precomp = Some(SenderPrecomputation::new(&self.ck, self.com));
+        }
+    }
+
+    /// Encrypt msgs[y] for index i, so that the receiver
+    /// learns exactly the message for its committed value y
+    pub fn send<R: Rng, M: AsRef<[u8]>>(
+        &self,
+        rng: &mut R,
+        i: usize,
+        msgs: &[M],
+    ) -> Result<MsgN<E>, LaconicError> {
+        check_n(msgs.len())?;
+        let size = self.ck.domain.size();
+        if i >= size {
+            return Err(LaconicError::IndexOutOfRange {
+                index: i,
+                length: size,
+            });
+        }
+        let x = self.ck.domain.element(i);
+
+        let pads: Vec<(E::ScalarField, PairingOutput<E>)> = match &self.precomp {
+            Some(precomp) => (0..msgs.len())
+                .map(|y| {
+                    let r = E::ScalarField::rand(rng);
+                    let y = E::ScalarField::from(y as u64);
+                    let msk = scalar_mul_with_precomputed_naf::<E>(
+                        precomp.naf(0),
+                        r * (E::ScalarField::one() - y),
+                    ) + scalar_mul_with_precomputed_naf::<E>(precomp.naf(1), r * y);
+                    (r, msk)
+                })
+                .collect(),
+            None => {
+                let com0 = E::pairing(self.com, self.ck.g2);
+                let com1 = E::pairing(self.com - self.ck.u[0], self.ck.g2);
+                (0..msgs.len())
+                    .map(|y| {
+                        let r = E::ScalarField::rand(rng);
+                        let y = E::ScalarField::from(y as u64);
+                        (r, com0 * (r * (E::ScalarField::one() - y)) + com1 * (r * y))
+                    })
+                    .collect()
+            }
+        };
+
+        // h = r * (tau - x)
+        let cm = Into::<E::G2>::into(self.ck.r) - self.ck.g2 * x;
+        let h = msgs
+            .iter()
+            .zip(pads)
+            .enumerate()
+            .map(|(j, (m, (r, msk)))| {
+                let ad = associated_data::<E>(&self.com, i, j);
+                ((cm * r).into(), encrypt::<E>(msk.0, &ad, m.as_ref()))
+            })
+            .collect();
+        Ok(MsgN { h })
+    }
+}
+
+impl<E: CurveId> MsgN<E> {
+    /// Encode the message in the wire format
+    pub fn to_bytes<D: EvaluationDomain<E::ScalarField>>(
+        &self,
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Vec<u8>, LaconicError> {
+        encode::<E, _>(Kind::MessageN, ck.domain.size(), self)
+    }
+
+    /// Decode a message produced for the same commitment key
+    pub fn from_bytes<D: EvaluationDomain<E::ScalarField>>(
+        bytes: &[u8],
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Self, LaconicError> {
+        let (domain_size, msg) = decode::<E, _>(Kind::MessageN, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        Ok(msg)
+    }
+}
+
+#[test]
+fn test_laconic_ot_n() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 4).unwrap();
+
+    // e.g. an 8-bit salary in a single OT
+    let values = [0u8, 255, 17, 42];
+    let receiver = LaconicOTNRecv::new(&ck, 256, &values).unwrap();
+    let mut sender = LaconicOTNSender::new(&ck, receiver.commitment());
+
+    let msgs: Vec<_> = (0..=255u8).map(|y| [y; 16]).collect();
+    let msg = sender.send(rng, 1, &msgs[..]).unwrap();
+    assert_eq!(receiver.recv(1, &msg).unwrap(), vec![255u8; 16]);
+
+    // the precomputed tables give the same result
+    sender.prepare();
+    let msg = sender.send(rng, 3, &msgs[..]).unwrap();
+    let msg = MsgN::from_bytes(&msg.to_bytes(&ck).unwrap(), &ck).unwrap();
+    assert_eq!(receiver.recv(3, &msg).unwrap(), vec![42u8; 16]);
+
+    // the message for the committed value is not decryptable at another index
+    assert_eq!(
+        receiver.recv(2, &msg).err(),
+        Some(LaconicError::AuthenticationFailed)
+    );
+}
+
+#[test]
+fn test_laconic_ot_n_reject_bad_input() {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    let rng = &mut test_rng();
+
+    let ck = CommitmentKey::<Bls12_381, Radix2EvaluationDomain<Fr>>::setup(rng, 4).unwrap();
+
+    let res = LaconicOTNRecv::new(&ck, 3, &[0, 1, 3]);
+    assert_eq!(
+        res.err(),
+        Some(LaconicError::ValueOutOfRange { value: 3, n: 3 })
+    );
+    let res = LaconicOTNRecv::new(&ck, 257, &[0]);
+    assert_eq!(res.err(), Some(LaconicError::InvalidValueCount { n: 257 }));
+
+    let receiver = LaconicOTNRecv::new(&ck, 3, &[0, 1, 2]).unwrap();
+    let sender = LaconicOTNSender::new(&ck, receiver.commitment());
+
+    // one message per value is required
+    let res = sender.send(rng, 0, &[[0u8; 16]]);
+    assert_eq!(res.err(), Some(LaconicError::InvalidValueCount { n: 1 }));
+    let msg = sender.send(rng, 0, &[[0u8; 16]; 4]).unwrap();
+    assert_eq!(
+        receiver.recv(0, &msg).err(),
+        Some(LaconicError::InvalidValueCount { n: 4 })
+    );
+}
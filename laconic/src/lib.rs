@@ -1,13 +1,31 @@
+mod ceremony;
+mod error;
+pub mod garble;
 mod kzg;
+mod kzg_bits;
+mod kzg_constraints;
 mod kzg_fk_open;
 mod kzg_types;
 mod kzg_utils;
+pub mod kzg_we;
 mod laconic_ot;
+mod laconic_ot_n;
+pub mod trinity;
 mod wasm_bindings;
+mod wire;
 
-pub use kzg_types::CommitmentKey;
+pub use bristol;
+pub use ceremony::{Ceremony, CeremonyContribution};
+pub use error::LaconicError;
+pub use kzg_bits::BitsProof;
+pub use kzg_constraints::{ConstraintProof, ConstraintSystem, Wire, RANDOM_ROWS};
+pub use kzg_types::{
+    Commitment, CommitmentKey, CommitmentKeyRef, MultiOpening, Opening, State, VcKZG,
+};
 pub use laconic_ot::*;
+pub use laconic_ot_n::{LaconicOTNRecv, LaconicOTNSender, MsgN, MAX_VALUES};
 pub use wasm_bindings::*;
+pub use wire::CurveId;
 
 // Initialize panic hook for better error messages in WASM
 #[wasm_bindgen::prelude::wasm_bindgen(start)]
@@ -0,0 +1,223 @@
+use ark_ec::pairing::Pairing;
+use ark_poly::EvaluationDomain;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
+use rand::Rng;
+
+use crate::error::LaconicError;
+use crate::garble::{self, GarbledCircuit, Label};
+use crate::kzg_types::{CommitmentKey, CommitmentKeyRef};
+use crate::laconic_ot::{LaconicOTRecv, LaconicOTSender, Msg};
+use crate::wire::{check_domain_size, decode, encode, CurveId, Kind};
+use bristol::Circuit;
+
+// Two-party computation of a Bristol circuit from a garbled circuit
+// and laconic OT:
+//
+// 1. the evaluator commits to its input bits and sends the commitment
+// 2. the garbler garbles the circuit and sends it together with the
+//    labels of its own input and, for every input wire of the evaluator,
+//    both labels encapsulated with laconic OT towards the commitment
+// 3. the evaluator decrypts the labels of its bits and evaluates
+//
+// The input of the garbler occupies the first input wires of the
+// circuit, the input of the evaluator the remaining ones. Bit i of the
+// evaluator is committed at index i. The receiver can also prove
+// statements about its bits before step 2, see Evaluator::receiver
+
+/// Everything the garbler sends to the evaluator
+#[derive(CanonicalSerialize, CanonicalDeserialize, Clone, Debug)]
+pub struct GarbledInput<E: Pairing> {
+    pub garbled: GarbledCircuit,
+
+    /// the labels of the input bits of the garbler
+    pub labels: Vec<Label>,
+
+    /// both labels of the ith input wire of the evaluator,
+    /// encapsulated for index i of the commitment
+    pub ot_msgs: Vec<Msg<E>>,
+}
+
+/// The party that evaluates the circuit
+pub struct Evaluator<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> {
+    receiver: LaconicOTRecv<'a, E, D>,
+}
+
+/// The party that garbles the circuit
+pub struct Garbler<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> {
+    ck: CommitmentKeyRef<'a, E, D>,
+}
+
+impl<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> Evaluator<'a, E, D> {
+    /// Commit to the input bits of the evaluator.
+    /// The key can be borrowed or shared via Arc
+    pub fn commit_inputs<K: Into<CommitmentKeyRef<'a, E, D>>>(
+        ck: K,
+        bits: &[bool],
+    ) -> Result<Self, LaconicError> {
+        Ok(Self {
+            receiver: LaconicOTRecv::new(ck, bits)?,
+        })
+    }
+
+    /// the commitment to send to the garbler
+    pub fn commitment(&self) -> E::G1 {
+        self.receiver.commitment()
+    }
+
+    /// The laconic OT receiver holding the committed bits,
+    /// e.g. to prove that they satisfy some constraints
+    pub fn receiver(&self) -> &LaconicOTRecv<'a, E, D> {
+        &self.receiver
+    }
+
+    /// Decrypt the labels of our bits and evaluate the garbled circuit.
+    /// Returns the bits of every output as in Circuit::evaluate
+    pub fn evaluate(
+        &self,
+        circuit: &Circuit,
+        input: &GarbledInput<E>,
+    ) -> Result<Vec<Vec<bool>>, LaconicError> {
+        let bits = self.receiver.bits();
+        if input.ot_msgs.len() != bits.len() {
+            return Err(LaconicError::LabelCountMismatch {
+                expected: bits.len(),
+                found: input.ot_msgs.len(),
+            });
+        }
+
+        let mut labels = input.labels.clone();
+        for (i, msg) in input.ot_msgs.iter().enumerate() {
+            let label = self.receiver.recv(i, msg)?;
+            labels.push(
+                label
+                    .try_into()
+                    .map_err(|_| LaconicError::Serialization("invalid label length".into()))?,
+            );
+        }
+        garble::evaluate(circuit, &input.garbled, &labels)
+    }
+}
+
+impl<'a, E: Pairing, D: EvaluationDomain<E::ScalarField>> Garbler<'a, E, D> {
+    /// The key can be borrowed or shared via Arc
+    pub fn new<K: Into<CommitmentKeyRef<'a, E, D>>>(ck: K) -> Self {
+        Self { ck: ck.into() }
+    }
+
+    /// Garble the circuit with fresh labels, encode our bits and send
+    /// the labels of the remaining input wires with laconic OT
+    pub fn garble_and_send<R: Rng>(
+        &self,
+        rng: &mut R,
+        circuit: &Circuit,
+        garbler_bits: &[bool],
+        evaluator_commitment: E::G1,
+    ) -> Result<GarbledInput<E>, LaconicError> {
+        let input_count: usize = circuit.input_widths.iter().sum();
+        if garbler_bits.len() > input_count {
+            return Err(LaconicError::LabelCountMismatch {
+                expected: input_count,
+                found: garbler_bits.len(),
+            });
+        }
+
+        let (garbled, encoding) = garble::garble(rng, circuit);
+        let labels = encoding.encode(0, garbler_bits)?;
+        let pairs = (garbler_bits.len()..input_count)
+            .map(|i| encoding.labels(i).map(|[l0, l1]| (l0, l1)))
+            .collect::<Result<Vec<_>, _>>()?;
+
+        let sender = LaconicOTSender::new(&*self.ck, evaluator_commitment);
+        let ot_msgs = sender.send_batch(rng, &pairs)?;
+        Ok(GarbledInput {
+            garbled,
+            labels,
+            ot_msgs,
+        })
+    }
+}
+
+impl<E: CurveId> GarbledInput<E> {
+    /// Encode the garbled circuit and the labels in the wire format
+    pub fn to_bytes<D: EvaluationDomain<E::ScalarField>>(
+        &self,
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Vec<u8>, LaconicError> {
+        encode::<E, _>(Kind::GarbledInput, ck.domain.size(), self)
+    }
+
+    /// Decode an input produced for the same commitment key
+    pub fn from_bytes<D: EvaluationDomain<E::ScalarField>>(
+        bytes: &[u8],
+        ck: &CommitmentKey<E, D>,
+    ) -> Result<Self, LaconicError> {
+        let (domain_size, input) = decode::<E, _>(Kind::GarbledInput, bytes)?;
+        check_domain_size(ck.domain.size(), domain_size)?;
+        Ok(input)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use ark_bls12_381::{Bls12_381, Fr};
+    use ark_poly::Radix2EvaluationDomain;
+    use ark_std::test_rng;
+
+    use super::{Evaluator, GarbledInput, Garbler};
+    use crate::error::LaconicError;
+    use crate::kzg_types::CommitmentKey;
+    use bristol::{parse_bits, Circuit};
+
+    type D = Radix2EvaluationDomain<Fr>;
+
+    #[test]
+    fn test_hiring() {
+        let rng = &mut test_rng();
+        let circuit =
+            Circuit::parse(include_str!("../../bristol/fixtures/job_matching.txt")).unwrap();
+        let ck = CommitmentKey::<Bls12_381, D>::setup(rng, 30).unwrap();
+
+        // the recruiter garbles, the candidate evaluates,
+        // see app/test/HIRING.md for the inputs
+        let recruiter = parse_bits("1 0 1000 00000001 0001 0100 00110010").unwrap();
+        let garbler = Garbler::new(&ck);
+        for (candidate, expected) in [
+            ("0 0 1000 00000001 0001 0100 00101101", true),
+            ("0 0 1000 00000001 0001 0100 00110111", false),
+        ] {
+            let candidate = parse_bits(candidate).unwrap();
+            let evaluator = Evaluator::commit_inputs(&ck, &candidate).unwrap();
+
+            let input = garbler
+                .garble_and_send(rng, &circuit, &recruiter, evaluator.commitment())
+                .unwrap();
+            let bytes = input.to_bytes(&ck).unwrap();
+            let input = GarbledInput::from_bytes(&bytes, &ck).unwrap();
+
+            let outputs = evaluator.evaluate(&circuit, &input).unwrap();
+            assert_eq!(outputs, vec![vec![expected]]);
+        }
+
+        // the garbler has to leave exactly the bits of the evaluator
+        let evaluator = Evaluator::commit_inputs(&ck, &recruiter[1..]).unwrap();
+        let input = garbler
+            .garble_and_send(rng, &circuit, &recruiter, evaluator.commitment())
+            .unwrap();
+        assert_eq!(
+            evaluator.evaluate(&circuit, &input),
+            Err(LaconicError::LabelCountMismatch {
+                expected: 29,
+                found: 30
+            })
+        );
+        assert_eq!(
+            garbler
+                .garble_and_send(rng, &circuit, &[false; 61], evaluator.commitment())
+                .err(),
+            Some(LaconicError::LabelCountMismatch {
+                expected: 60,
+                found: 61
+            })
+        );
+    }
+}
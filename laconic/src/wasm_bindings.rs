@@ -1,68 +1,379 @@
-use ark_bls12_381::{Bls12_381, Fr};
+use ark_bls12_377::Bls12_377;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
 use ark_ec::pairing::Pairing;
-use ark_poly::Radix2EvaluationDomain;
+use ark_ec::AffineRepr;
+use ark_ff::PrimeField;
+use ark_poly::{EvaluationDomain, Radix2EvaluationDomain};
 use ark_serialize::{CanonicalDeserialize, CanonicalSerialize};
-use rand::{thread_rng, Rng};
-use std::convert::TryInto;
+use std::sync::Arc;
 use wasm_bindgen::prelude::*;
 
-use crate::{CommitmentKey, LaconicOTRecv, LaconicOTSender, Msg, MSG_SIZE};
+use crate::garble::{self, Encoding, GarbledCircuit, Label, LABEL_SIZE};
+use crate::kzg_utils::deserialize_point;
+use crate::wire::{check_domain_size, curve_of, decode, decode_point, encode, Kind};
+use crate::{
+    kzg_we, BitsProof, Ceremony, CommitmentKey, ConstraintProof, ConstraintSystem, CurveId,
+    LaconicError, LaconicOTRecv, LaconicOTSender, Msg, Opening, SenderPrecomputation,
+};
+use bristol::{BristolError, Circuit};
 
-type Domain = Radix2EvaluationDomain<Fr>;
-type E = Bls12_381;
+// The curve is selected at runtime. Keys are created for a curve
+// given by its id in the wire format (BLS12-381 by default), every
+// other object takes the curve of the key it is created with, and
+// decoding reads the curve from the header. Objects for different
+// curves cannot be mixed
+
+type Domain<E> = Radix2EvaluationDomain<<E as Pairing>::ScalarField>;
+
+/// A value for one of the supported curves
+#[derive(Clone)]
+enum Curves<A, B, C> {
+    Bls12_381(A),
+    Bn254(B),
+    Bls12_377(C),
+}
+
+impl<A, B, C> Curves<A, B, C> {
+    fn curve_id(&self) -> u8 {
+        match self {
+            Curves::Bls12_381(_) => Bls12_381::CURVE_ID,
+            Curves::Bn254(_) => Bn254::CURVE_ID,
+            Curves::Bls12_377(_) => Bls12_377::CURVE_ID,
+        }
+    }
+}
+
+/// Curves with T<E> for each of the curves
+macro_rules! per_curve {
+    ($t:ident) => {
+        Curves<$t<Bls12_381>, $t<Bn254>, $t<Bls12_377>>
+    };
+}
+
+/// Evaluate body on the value inside curves. In body, E is the
+/// pairing of the value and Curve wraps a value for the same curve
+macro_rules! on_curve {
+    ($curves:expr, |$x:pat_param| $body:expr) => {
+        match $curves {
+            Curves::Bls12_381($x) => on_curve!(@with Bls12_381, $body),
+            Curves::Bn254($x) => on_curve!(@with Bn254, $body),
+            Curves::Bls12_377($x) => on_curve!(@with Bls12_377, $body),
+        }
+    };
+    (@with $curve:ident, $body:expr) => {{
+        #[allow(dead_code)]
+        type E = $curve;
+        #[allow(unused_imports)]
+        use Curves::$curve as Curve;
+        $body
+    }};
+}
+
+/// Evaluate body for the curve with the given id, as on_curve
+macro_rules! with_curve {
+    ($id:expr, $body:expr) => {
+        match $id {
+            id if id == Bls12_381::CURVE_ID => on_curve!(@with Bls12_381, $body),
+            id if id == Bn254::CURVE_ID => on_curve!(@with Bn254, $body),
+            id if id == Bls12_377::CURVE_ID => on_curve!(@with Bls12_377, $body),
+            id => Err(LaconicError::UnsupportedCurve { id }.into()),
+        }
+    };
+}
+
+/// The value inside curves if it is for the curve E selected by
+/// on_curve, otherwise returns a CurveMismatch error
+macro_rules! same_curve {
+    ($curves:expr) => {
+        match $curves {
+            Curve(x) => x,
+            other => {
+                return Err(LaconicError::CurveMismatch {
+                    expected: <E as CurveId>::CURVE_ID,
+                    found: other.curve_id(),
+                }
+                .into())
+            }
+        }
+    };
+}
+
+type Key<E> = Arc<CommitmentKey<E, Domain<E>>>;
+type Receiver<E> = LaconicOTRecv<'static, E, Domain<E>>;
+type Sender<E> = LaconicOTSender<'static, E, Domain<E>>;
+type WeOpening<E> = (<E as Pairing>::ScalarField, Opening<E>);
+type WeCiphertext<E> = kzg_we::Ciphertext<E>;
 
 // Wrapper types for WASM
 #[wasm_bindgen]
 #[derive(Clone)]
 pub struct WasmCommitmentKey {
-    commitment_key: CommitmentKey<E, Domain>,
+    // shared with all receivers and senders created from it
+    commitment_key: per_curve!(Key),
 }
 
 #[wasm_bindgen]
 pub struct WasmReceiver {
-    receiver: LaconicOTRecv<'static, E, Domain>,
+    receiver: per_curve!(Receiver),
 }
 
 #[wasm_bindgen]
 pub struct WasmSender {
-    sender: LaconicOTSender<'static, E, Domain>,
+    sender: per_curve!(Sender),
 }
 
 #[wasm_bindgen]
+pub struct WasmCeremony {
+    ceremony: per_curve!(Ceremony),
+}
+
+#[wasm_bindgen]
+#[derive(Clone)]
 pub struct WasmMessage {
-    message: Msg<E>,
+    message: per_curve!(Msg),
+}
+
+#[wasm_bindgen]
+pub struct WasmWePolynomial {
+    // the encoded evaluations, which are decoded
+    // for the curve of the key they are used with
+    evals: Vec<u8>,
+}
+
+#[wasm_bindgen]
+pub struct WasmWeOpening {
+    opening: per_curve!(WeOpening),
+}
+
+#[wasm_bindgen]
+pub struct WasmWeCiphertext {
+    ciphertext: per_curve!(WeCiphertext),
+}
+
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmMessageBatch {
+    messages: Vec<WasmMessage>,
+}
+
+/// Statement about the bits of a receiver, agreed on by both parties
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct WasmConstraintSystem {
+    // the constraint system is built over the
+    // scalar field of the key it is used with
+    statements: Vec<Statement>,
+}
+
+#[derive(Clone, Copy)]
+enum Statement {
+    Bits(usize, usize),
+    Fixed(usize, bool),
+    PrefixOnes(usize, usize),
+    SuffixOnes(usize, usize),
+    AtMost(usize, usize, u32),
+    AtLeast(usize, usize, u32),
+}
+
+/// A Bristol circuit, known to both parties
+#[wasm_bindgen]
+pub struct WasmCircuit {
+    circuit: Circuit,
+}
+
+/// Holds the garbled circuit and the input labels of the garbler
+#[wasm_bindgen]
+pub struct WasmGarbler {
+    garbled: GarbledCircuit,
+    encoding: Encoding,
+}
+
+#[wasm_bindgen]
+pub struct WasmGarbledCircuit {
+    garbled: GarbledCircuit,
 }
 
 // CommitmentKey implementations
 #[wasm_bindgen]
 impl WasmCommitmentKey {
+    /// Set up a key on BLS12-381
     #[wasm_bindgen]
-    pub fn setup(message_length: usize) -> Result<WasmCommitmentKey, JsValue> {
+    pub fn setup(message_length: usize) -> Result<WasmCommitmentKey, JsError> {
+        Self::setup_for_curve(Bls12_381::CURVE_ID, message_length)
+    }
+
+    /// Set up a key on the curve with the given id, see curve_id
+    #[wasm_bindgen]
+    pub fn setup_for_curve(
+        curve_id: u8,
+        message_length: usize,
+    ) -> Result<WasmCommitmentKey, JsError> {
         let mut rng = rand::thread_rng();
 
-        CommitmentKey::<E, Domain>::setup(&mut rng, message_length)
-            .map(|key| WasmCommitmentKey {
-                commitment_key: key,
+        with_curve!(curve_id, {
+            let key = CommitmentKey::<E, Domain<E>>::setup(&mut rng, message_length)?;
+            Ok(WasmCommitmentKey {
+                commitment_key: Curve(Arc::new(key)),
             })
-            .map_err(|_| JsError::new("Failed to setup commitment key").into())
+        })
+    }
+
+    /// Build a commitment key on BLS12-381 from a public powers-of-tau
+    /// transcript. powers is the concatenation of the uncompressed G1
+    /// powers of g1, g2 and g2_alpha are uncompressed G2 points.
+    /// The key works for laconic OT, but has no hiding part for
+    /// the receiver proofs, see WasmCeremony for a key that has one
+    #[wasm_bindgen]
+    pub fn from_powers_of_tau(
+        message_length: usize,
+        powers: &[u8],
+        g2: &[u8],
+        g2_alpha: &[u8],
+    ) -> Result<WasmCommitmentKey, JsError> {
+        Self::from_powers_of_tau_for_curve(
+            Bls12_381::CURVE_ID,
+            message_length,
+            powers,
+            g2,
+            g2_alpha,
+        )
+    }
+
+    /// As from_powers_of_tau, for the curve with the given id
+    #[wasm_bindgen]
+    pub fn from_powers_of_tau_for_curve(
+        curve_id: u8,
+        message_length: usize,
+        powers: &[u8],
+        g2: &[u8],
+        g2_alpha: &[u8],
+    ) -> Result<WasmCommitmentKey, JsError> {
+        with_curve!(curve_id, {
+            let powers = deserialize_g1_points::<E>(powers)?;
+            let g2 = deserialize_point::<<E as Pairing>::G2Affine>(g2)?;
+            let g2_alpha = deserialize_point::<<E as Pairing>::G2Affine>(g2_alpha)?;
+
+            let key = CommitmentKey::<E, Domain<E>>::from_powers_of_tau(
+                message_length,
+                &powers,
+                g2,
+                g2_alpha,
+            )?;
+            Ok(WasmCommitmentKey {
+                commitment_key: Curve(Arc::new(key)),
+            })
+        })
+    }
+
+    /// The id of the curve of the key, as in the wire format:
+    /// 1 for BLS12-381, 2 for BN254 and 3 for BLS12-377
+    #[wasm_bindgen]
+    pub fn curve_id(&self) -> u8 {
+        self.commitment_key.curve_id()
+    }
+
+    /// Check that the key is well-formed, e.g. after receiving it
+    /// from the other party. Uses the randomized batched checks
+    #[wasm_bindgen]
+    pub fn verify(&self) -> Result<(), JsError> {
+        let mut rng = rand::thread_rng();
+        on_curve!(&self.commitment_key, |ck| Ok(ck.verify_batched(&mut rng)?))
     }
 
     #[wasm_bindgen]
-    pub fn serialize(&self) -> Vec<u8> {
-        let mut bytes = Vec::new();
-        self.commitment_key
-            .serialize_uncompressed(&mut bytes)
-            .unwrap();
-        bytes
+    pub fn serialize(&self) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.commitment_key, |ck| Ok(ck.to_bytes()?))
     }
 
+    /// Decode a key for any of the curves
     #[wasm_bindgen]
-    pub fn deserialize(data: &[u8]) -> Result<WasmCommitmentKey, JsValue> {
-        CommitmentKey::<E, Domain>::deserialize_uncompressed(data)
-            .map(|key| WasmCommitmentKey {
-                commitment_key: key,
+    pub fn deserialize(data: &[u8]) -> Result<WasmCommitmentKey, JsError> {
+        with_curve!(curve_of(data)?, {
+            let key = CommitmentKey::<E, Domain<E>>::from_bytes(data)?;
+            Ok(WasmCommitmentKey {
+                commitment_key: Curve(Arc::new(key)),
             })
-            .map_err(|_| JsError::new("Failed to deserialize commitment key").into())
+        })
+    }
+}
+
+/// concatenated uncompressed G1 points
+fn deserialize_g1_points<E: Pairing>(bytes: &[u8]) -> Result<Vec<E::G1Affine>, LaconicError> {
+    let size = E::G1Affine::zero().uncompressed_size();
+    if !bytes.len().is_multiple_of(size) {
+        return Err(LaconicError::MalformedPoint);
+    }
+    bytes
+        .chunks(size)
+        .map(deserialize_point::<E::G1Affine>)
+        .collect()
+}
+
+// Ceremony implementations
+#[wasm_bindgen]
+impl WasmCeremony {
+    /// Start a ceremony on BLS12-381
+    #[wasm_bindgen]
+    pub fn new(message_length: usize) -> Result<WasmCeremony, JsError> {
+        Self::new_for_curve(Bls12_381::CURVE_ID, message_length)
+    }
+
+    /// Start a ceremony on the curve with the given id
+    #[wasm_bindgen]
+    pub fn new_for_curve(curve_id: u8, message_length: usize) -> Result<WasmCeremony, JsError> {
+        with_curve!(curve_id, {
+            Ok(WasmCeremony {
+                ceremony: Curve(Ceremony::<E>::new::<Domain<E>>(message_length)?),
+            })
+        })
+    }
+
+    /// Add our contribution. Call verify on a received ceremony first
+    #[wasm_bindgen]
+    pub fn contribute(&mut self) {
+        let mut rng = rand::thread_rng();
+        on_curve!(&mut self.ceremony, |ceremony| {
+            ceremony.contribute(&mut rng);
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn verify(&self) -> Result<(), JsError> {
+        let mut rng = rand::thread_rng();
+        on_curve!(&self.ceremony, |ceremony| Ok(ceremony.verify(&mut rng)?))
+    }
+
+    /// Verify the ceremony and derive the commitment key from it
+    #[wasm_bindgen]
+    pub fn commitment_key(&self) -> Result<WasmCommitmentKey, JsError> {
+        let mut rng = rand::thread_rng();
+        on_curve!(&self.ceremony, |ceremony| {
+            let key = ceremony.commitment_key::<Domain<E>, _>(&mut rng)?;
+            Ok(WasmCommitmentKey {
+                commitment_key: Curve(Arc::new(key)),
+            })
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.ceremony, |ceremony| Ok(encode::<E, _>(
+            Kind::Ceremony,
+            ceremony.powers.len(),
+            ceremony
+        )?))
+    }
+
+    /// Decode a ceremony for any of the curves
+    #[wasm_bindgen]
+    pub fn deserialize(data: &[u8]) -> Result<WasmCeremony, JsError> {
+        with_curve!(curve_of(data)?, {
+            let (_, ceremony) = decode::<E, Ceremony<E>>(Kind::Ceremony, data)?;
+            Ok(WasmCeremony {
+                ceremony: Curve(ceremony),
+            })
+        })
     }
 }
 
@@ -70,40 +381,74 @@ impl WasmCommitmentKey {
 #[wasm_bindgen]
 impl WasmReceiver {
     #[wasm_bindgen]
-    pub fn new(ck: &WasmCommitmentKey, bits: Vec<u8>) -> Self {
-        let key = Box::leak(Box::new(ck.commitment_key.clone()));
+    pub fn new(ck: &WasmCommitmentKey, bits: Vec<u8>) -> Result<WasmReceiver, JsError> {
         let bits: Vec<bool> = bits.into_iter().map(|b| b != 0).collect();
-        WasmReceiver {
-            receiver: LaconicOTRecv::new(key, &bits),
-        }
+        on_curve!(&ck.commitment_key, |ck| Ok(WasmReceiver {
+            receiver: Curve(LaconicOTRecv::new(ck.clone(), &bits)?),
+        }))
     }
 
     #[wasm_bindgen]
-    pub fn recv(&self, i: usize, msg: &WasmMessage) -> Vec<u8> {
-        self.receiver.recv(i, msg.message.clone()).to_vec()
+    pub fn recv(&self, i: usize, msg: &WasmMessage) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.receiver, |receiver| Ok(
+            receiver.recv(i, same_curve!(&msg.message))?
+        ))
     }
 
+    /// Change the ith bit without recomputing the whole commitment.
+    /// The commitment has to be sent to the sender again afterwards
     #[wasm_bindgen]
-    pub fn commitment(&self) -> Vec<u8> {
-        let mut bytes = Vec::new();
-        self.receiver
-            .commitment()
-            .serialize_uncompressed(&mut bytes)
-            .unwrap();
-        bytes
+    pub fn update(&mut self, i: usize, bit: u8) -> Result<(), JsError> {
+        on_curve!(&mut self.receiver, |receiver| Ok(
+            receiver.update(i, bit != 0)?
+        ))
     }
 
     #[wasm_bindgen]
-    pub fn deserialize(data: &[u8], ck: &WasmCommitmentKey) -> Self {
-        let key = Box::leak(Box::new(ck.commitment_key.clone()));
-        WasmReceiver {
-            receiver: LaconicOTRecv::deserialize(data, key),
-        }
+    pub fn commitment(&self) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.receiver, |receiver| Ok(
+            receiver.commitment_to_bytes()?
+        ))
     }
 
+    /// Prove that the commitment holds only bits, see WasmSender::verify_bits.
+    /// Fails once the proofs used up the padding of the commitment
     #[wasm_bindgen]
-    pub fn serialize(&self) -> Vec<u8> {
-        self.receiver.serialize()
+    pub fn prove_bits(&mut self, ck: &WasmCommitmentKey) -> Result<Vec<u8>, JsError> {
+        let mut rng = rand::thread_rng();
+        on_curve!(&mut self.receiver, |receiver| {
+            let ck = same_curve!(&ck.commitment_key);
+            let proof = receiver.prove_bits(&mut rng)?;
+            Ok(proof.to_bytes(ck)?)
+        })
+    }
+
+    /// Prove that the bits satisfy cs, see WasmSender::verify_constraints.
+    /// Shares the limit on the number of proofs with prove_bits
+    #[wasm_bindgen]
+    pub fn prove_constraints(
+        &mut self,
+        ck: &WasmCommitmentKey,
+        cs: &WasmConstraintSystem,
+    ) -> Result<Vec<u8>, JsError> {
+        let mut rng = rand::thread_rng();
+        on_curve!(&mut self.receiver, |receiver| {
+            let ck = same_curve!(&ck.commitment_key);
+            let proof = receiver.prove_constraints(&mut rng, &cs.build())?;
+            Ok(proof.to_bytes(ck)?)
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(data: &[u8], ck: &WasmCommitmentKey) -> Result<WasmReceiver, JsError> {
+        on_curve!(&ck.commitment_key, |ck| Ok(WasmReceiver {
+            receiver: Curve(LaconicOTRecv::deserialize(data, ck.clone())?),
+        }))
+    }
+
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.receiver, |receiver| Ok(receiver.serialize()?))
     }
 }
 
@@ -111,28 +456,485 @@ impl WasmReceiver {
 #[wasm_bindgen]
 impl WasmSender {
     #[wasm_bindgen]
-    pub fn new(ck: &WasmCommitmentKey, commitment_bytes: &[u8]) -> Result<WasmSender, JsValue> {
-        let commitment = <E as Pairing>::G1::deserialize_uncompressed(commitment_bytes)
-            .map_err(|_| JsError::new("Failed to deserialize commitment"))?;
-        let key = Box::leak(Box::new(ck.commitment_key.clone()));
-        Ok(WasmSender {
-            sender: LaconicOTSender::new(key, commitment),
+    pub fn new(ck: &WasmCommitmentKey, commitment_bytes: &[u8]) -> Result<WasmSender, JsError> {
+        on_curve!(&ck.commitment_key, |ck| Ok(WasmSender {
+            sender: Curve(LaconicOTSender::from_commitment_bytes(
+                ck.clone(),
+                commitment_bytes,
+            )?),
+        }))
+    }
+
+    /// Precompute and cache the pairing tables, which
+    /// makes every subsequent send considerably cheaper
+    #[wasm_bindgen]
+    pub fn prepare(&mut self) {
+        on_curve!(&mut self.sender, |sender| sender.prepare())
+    }
+
+    /// Serialize the cached pairing tables (computing them if needed),
+    /// e.g. to store them or to pass them between workers
+    #[wasm_bindgen]
+    pub fn export_precomputation(&mut self) -> Result<Vec<u8>, JsError> {
+        on_curve!(&mut self.sender, |sender| {
+            sender.prepare();
+            let mut bytes = Vec::new();
+            if let Some(precomp) = sender.precomputation() {
+                precomp
+                    .serialize_compressed(&mut bytes)
+                    .map_err(LaconicError::from)?;
+            }
+            Ok(bytes)
+        })
+    }
+
+    /// Load pairing tables produced by export_precomputation.
+    /// They must have been computed for the same commitment
+    #[wasm_bindgen]
+    pub fn import_precomputation(&mut self, data: &[u8]) -> Result<(), JsError> {
+        on_curve!(&mut self.sender, |sender| {
+            // the tables may have been stored outside of our control,
+            // so the target group elements are checked as well
+            let precomp = SenderPrecomputation::<E>::deserialize_compressed(data)
+                .map_err(LaconicError::from)?;
+            sender.set_precomputation(precomp)?;
+            Ok(())
         })
     }
 
+    /// Check that the receiver committed to bits on the first length
+    /// points, before sending any labels
     #[wasm_bindgen]
-    pub fn send(&self, i: usize, m0: &[u8], m1: &[u8]) -> Result<WasmMessage, JsValue> {
+    pub fn verify_bits(
+        &self,
+        ck: &WasmCommitmentKey,
+        length: usize,
+        proof: &[u8],
+    ) -> Result<bool, JsError> {
+        on_curve!(&self.sender, |sender| {
+            let proof = BitsProof::from_bytes(proof, same_curve!(&ck.commitment_key))?;
+            Ok(sender.verify_bits(length, &proof))
+        })
+    }
+
+    /// Check that the bits of the receiver satisfy cs, before sending any labels
+    #[wasm_bindgen]
+    pub fn verify_constraints(
+        &self,
+        ck: &WasmCommitmentKey,
+        cs: &WasmConstraintSystem,
+        proof: &[u8],
+    ) -> Result<bool, JsError> {
+        on_curve!(&self.sender, |sender| {
+            let proof = ConstraintProof::from_bytes(proof, same_curve!(&ck.commitment_key))?;
+            Ok(sender.verify_constraints(&cs.build(), &proof))
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn send(&self, i: usize, m0: &[u8], m1: &[u8]) -> Result<WasmMessage, JsError> {
         let mut rng = rand::thread_rng();
 
-        let m0_array: [u8; MSG_SIZE] = m0
-            .try_into()
-            .map_err(|_| JsError::new(&format!("m0 must be {} bytes", MSG_SIZE)))?;
-        let m1_array: [u8; MSG_SIZE] = m1
-            .try_into()
-            .map_err(|_| JsError::new(&format!("m1 must be {} bytes", MSG_SIZE)))?;
+        on_curve!(&self.sender, |sender| Ok(WasmMessage {
+            message: Curve(sender.send(&mut rng, i, m0, m1)?),
+        }))
+    }
+}
+
+// Constraint system implementations
+#[wasm_bindgen]
+impl WasmConstraintSystem {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmConstraintSystem {
+        WasmConstraintSystem::default()
+    }
+
+    #[wasm_bindgen]
+    pub fn bits(&mut self, start: usize, len: usize) {
+        self.statements.push(Statement::Bits(start, len));
+    }
+
+    #[wasm_bindgen]
+    pub fn fixed(&mut self, row: usize, bit: u8) {
+        self.statements.push(Statement::Fixed(row, bit != 0));
+    }
+
+    /// ones followed by zeros, as produced by convertToVector for candidates
+    #[wasm_bindgen(js_name = prefixOnes)]
+    pub fn prefix_ones(&mut self, start: usize, len: usize) {
+        self.statements.push(Statement::PrefixOnes(start, len));
+    }
+
+    /// zeros followed by ones, as produced by convertToVector for recruiters
+    #[wasm_bindgen(js_name = suffixOnes)]
+    pub fn suffix_ones(&mut self, start: usize, len: usize) -> Result<(), JsError> {
+        if len == 0 {
+            return Err(JsError::new("the suffix of ones is not empty"));
+        }
+        self.statements.push(Statement::SuffixOnes(start, len));
+        Ok(())
+    }
+
+    #[wasm_bindgen(js_name = atMost)]
+    pub fn at_most(&mut self, start: usize, len: usize, bound: u32) -> Result<(), JsError> {
+        check_comparison(len, bound)?;
+        self.statements.push(Statement::AtMost(start, len, bound));
+        Ok(())
+    }
 
-        let msg = self.sender.send(&mut rng, i, m0_array, m1_array);
-        Ok(WasmMessage { message: msg })
+    #[wasm_bindgen(js_name = atLeast)]
+    pub fn at_least(&mut self, start: usize, len: usize, bound: u32) -> Result<(), JsError> {
+        check_comparison(len, bound)?;
+        self.statements.push(Statement::AtLeast(start, len, bound));
+        Ok(())
+    }
+}
+
+impl WasmConstraintSystem {
+    fn build<F: PrimeField>(&self) -> ConstraintSystem<F> {
+        let mut cs = ConstraintSystem::new();
+        for statement in self.statements.iter() {
+            match *statement {
+                Statement::Bits(start, len) => cs.bits(start, len),
+                Statement::Fixed(row, bit) => cs.fixed(row, bit),
+                Statement::PrefixOnes(start, len) => cs.prefix_ones(start, len),
+                Statement::SuffixOnes(start, len) => cs.suffix_ones(start, len),
+                Statement::AtMost(start, len, bound) => cs.at_most(start, len, bound.into()),
+                Statement::AtLeast(start, len, bound) => cs.at_least(start, len, bound.into()),
+            };
+        }
+        cs
+    }
+}
+
+/// the bound of a comparison has to fit into the given number of bits
+fn check_comparison(len: usize, bound: u32) -> Result<(), JsError> {
+    if !(1..=32).contains(&len) || u64::from(bound) >> len != 0 {
+        return Err(JsError::new("bound does not fit into the bits"));
+    }
+    Ok(())
+}
+
+// Message implementations
+#[wasm_bindgen]
+impl WasmMessage {
+    #[wasm_bindgen]
+    pub fn serialize(&self, ck: &WasmCommitmentKey) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.message, |message| Ok(
+            message.to_bytes(same_curve!(&ck.commitment_key))?
+        ))
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(data: &[u8], ck: &WasmCommitmentKey) -> Result<WasmMessage, JsError> {
+        on_curve!(&ck.commitment_key, |ck| Ok(WasmMessage {
+            message: Curve(Msg::from_bytes(data, ck)?),
+        }))
+    }
+}
+
+/// A list of messages, so that all OT messages
+/// can be sent to the receiver in one go
+#[wasm_bindgen]
+impl WasmMessageBatch {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmMessageBatch {
+        WasmMessageBatch::default()
+    }
+
+    #[wasm_bindgen]
+    pub fn push(&mut self, msg: &WasmMessage) {
+        self.messages.push(msg.clone());
+    }
+
+    #[wasm_bindgen]
+    pub fn get(&self, i: usize) -> Result<WasmMessage, JsError> {
+        let message = self
+            .messages
+            .get(i)
+            .ok_or(LaconicError::IndexOutOfRange {
+                index: i,
+                length: self.messages.len(),
+            })?
+            .clone();
+        Ok(message)
+    }
+
+    #[wasm_bindgen]
+    pub fn len(&self) -> usize {
+        self.messages.len()
+    }
+
+    #[wasm_bindgen(js_name = isEmpty)]
+    pub fn is_empty(&self) -> bool {
+        self.messages.is_empty()
+    }
+
+    /// Fails if a message is for another curve than ck
+    #[wasm_bindgen]
+    pub fn serialize(&self, ck: &WasmCommitmentKey) -> Result<Vec<u8>, JsError> {
+        on_curve!(&ck.commitment_key, |ck| {
+            let mut messages = Vec::with_capacity(self.messages.len());
+            for msg in self.messages.iter() {
+                messages.push(same_curve!(&msg.message).clone());
+            }
+            Ok(Msg::batch_to_bytes(&messages, ck)?)
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(data: &[u8], ck: &WasmCommitmentKey) -> Result<WasmMessageBatch, JsError> {
+        on_curve!(&ck.commitment_key, |ck| Ok(WasmMessageBatch {
+            messages: Msg::batch_from_bytes(data, ck)?
+                .into_iter()
+                .map(|message| WasmMessage {
+                    message: Curve(message),
+                })
+                .collect(),
+        }))
+    }
+}
+
+// Garbled circuit implementations.
+// Labels are passed as LABEL_SIZE bytes each, several
+// labels as their concatenation
+#[wasm_bindgen]
+impl WasmCircuit {
+    /// Parse a circuit in Bristol format, e.g. job_matching.txt
+    #[wasm_bindgen]
+    pub fn parse(text: &str) -> Result<WasmCircuit, JsError> {
+        Ok(WasmCircuit {
+            circuit: Circuit::parse(text)?,
+        })
+    }
+
+    /// the number of bits of each input
+    #[wasm_bindgen]
+    pub fn input_widths(&self) -> Vec<u32> {
+        self.circuit
+            .input_widths
+            .iter()
+            .map(|w| *w as u32)
+            .collect()
+    }
+
+    /// Evaluate the circuit in plaintext, bits has one byte per input wire.
+    /// Returns one byte per output wire
+    #[wasm_bindgen]
+    pub fn evaluate(&self, bits: &[u8]) -> Result<Vec<u8>, JsError> {
+        let mut bits = bits.iter().map(|b| *b != 0);
+        let inputs: Vec<Vec<bool>> = self
+            .circuit
+            .input_widths
+            .iter()
+            .map(|width| bits.by_ref().take(*width).collect())
+            .collect();
+        if bits.next().is_some() {
+            return Err(BristolError::InputMismatch {
+                index: inputs.len(),
+            }
+            .into());
+        }
+        let outputs = self.circuit.evaluate(&inputs)?;
+        Ok(outputs.concat().into_iter().map(u8::from).collect())
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGarbler {
+    /// Garble the circuit with fresh labels
+    #[wasm_bindgen(constructor)]
+    pub fn new(circuit: &WasmCircuit) -> WasmGarbler {
+        let mut rng = rand::thread_rng();
+        let (garbled, encoding) = garble::garble(&mut rng, &circuit.circuit);
+        WasmGarbler { garbled, encoding }
+    }
+
+    /// The garbled circuit to send to the evaluator
+    #[wasm_bindgen]
+    pub fn garbled_circuit(&self) -> WasmGarbledCircuit {
+        WasmGarbledCircuit {
+            garbled: self.garbled.clone(),
+        }
+    }
+
+    /// The label of the bit on the ith input wire
+    #[wasm_bindgen]
+    pub fn label(&self, i: usize, bit: u8) -> Result<Vec<u8>, JsError> {
+        Ok(self.encoding.label(i, bit != 0)?.to_vec())
+    }
+
+    /// The labels of the bits of an input starting at wire start,
+    /// e.g. the input of the garbler
+    #[wasm_bindgen]
+    pub fn encode(&self, start: usize, bits: Vec<u8>) -> Result<Vec<u8>, JsError> {
+        let bits: Vec<bool> = bits.into_iter().map(|b| b != 0).collect();
+        Ok(self.encoding.encode(start, &bits)?.concat())
+    }
+}
+
+#[wasm_bindgen]
+impl WasmGarbledCircuit {
+    /// Evaluate on the concatenated labels of all input wires.
+    /// Returns one byte per output wire
+    #[wasm_bindgen]
+    pub fn evaluate(&self, circuit: &WasmCircuit, labels: &[u8]) -> Result<Vec<u8>, JsError> {
+        if !labels.len().is_multiple_of(LABEL_SIZE) {
+            return Err(LaconicError::Serialization("invalid length of labels".into()).into());
+        }
+        let labels: Vec<Label> = labels
+            .chunks(LABEL_SIZE)
+            .map(|l| l.try_into().unwrap())
+            .collect();
+        let outputs = garble::evaluate(&circuit.circuit, &self.garbled, &labels)?;
+        Ok(outputs.concat().into_iter().map(u8::from).collect())
+    }
+
+    // garbled circuits do not depend on the curve
+    #[wasm_bindgen]
+    pub fn serialize(&self) -> Result<Vec<u8>, JsError> {
+        Ok(self.garbled.to_bytes()?)
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(data: &[u8]) -> Result<WasmGarbledCircuit, JsError> {
+        Ok(WasmGarbledCircuit {
+            garbled: GarbledCircuit::from_bytes(data)?,
+        })
+    }
+}
+
+// Witness encryption implementations.
+// Field elements are passed as their canonical
+// little-endian encoding of FIELD_SIZE bytes,
+// which is the same for the scalar fields of all curves
+const FIELD_SIZE: usize = 32;
+
+fn field_elements<F: PrimeField>(bytes: &[u8]) -> Result<Vec<F>, LaconicError> {
+    bytes.chunks(FIELD_SIZE).map(field_element).collect()
+}
+
+fn field_element<F: PrimeField>(bytes: &[u8]) -> Result<F, LaconicError> {
+    Ok(F::deserialize_compressed(bytes)?)
+}
+
+fn field_bytes<F: PrimeField>(x: &F) -> Result<Vec<u8>, LaconicError> {
+    let mut bytes = Vec::with_capacity(FIELD_SIZE);
+    x.serialize_compressed(&mut bytes)?;
+    Ok(bytes)
+}
+
+#[wasm_bindgen]
+impl WasmWePolynomial {
+    /// evals is the concatenation of the evaluations over the domain
+    #[wasm_bindgen(constructor)]
+    pub fn new(evals: &[u8]) -> Result<WasmWePolynomial, JsError> {
+        if !evals.len().is_multiple_of(FIELD_SIZE) {
+            return Err(LaconicError::Serialization("invalid length of evaluations".into()).into());
+        }
+        Ok(WasmWePolynomial {
+            evals: evals.to_vec(),
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn commitment(&self, ck: &WasmCommitmentKey) -> Result<Vec<u8>, JsError> {
+        on_curve!(&ck.commitment_key, |ck| {
+            let com = kzg_we::commit(ck, &field_elements(&self.evals)?)?;
+            Ok(encode::<E, _>(Kind::Commitment, ck.domain.size(), &com)?)
+        })
+    }
+
+    /// Evaluate at z and prove the evaluation
+    #[wasm_bindgen]
+    pub fn open(&self, ck: &WasmCommitmentKey, z: &[u8]) -> Result<WasmWeOpening, JsError> {
+        on_curve!(&ck.commitment_key, |ck| {
+            let evals = field_elements(&self.evals)?;
+            Ok(WasmWeOpening {
+                opening: Curve(kzg_we::open(ck, &evals, field_element(z)?)?),
+            })
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl WasmWeOpening {
+    /// the evaluation f(z) that the opening proves
+    #[wasm_bindgen]
+    pub fn value(&self) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.opening, |(value, _)| Ok(field_bytes(value)?))
+    }
+
+    #[wasm_bindgen]
+    pub fn serialize(&self, ck: &WasmCommitmentKey) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.opening, |opening| {
+            let ck = same_curve!(&ck.commitment_key);
+            Ok(encode::<E, _>(Kind::Opening, ck.domain.size(), opening)?)
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(data: &[u8], ck: &WasmCommitmentKey) -> Result<WasmWeOpening, JsError> {
+        on_curve!(&ck.commitment_key, |ck| {
+            let (domain_size, opening) = decode::<E, WeOpening<E>>(Kind::Opening, data)?;
+            check_domain_size(ck.domain.size(), domain_size)?;
+            Ok(WasmWeOpening {
+                opening: Curve(opening),
+            })
+        })
+    }
+}
+
+#[wasm_bindgen]
+impl WasmWeCiphertext {
+    /// Encrypt msg towards (com, z, y), so that it can be
+    /// decrypted with an opening proving f(z) = y
+    #[wasm_bindgen]
+    pub fn encrypt(
+        ck: &WasmCommitmentKey,
+        com: &[u8],
+        z: &[u8],
+        y: &[u8],
+        msg: &[u8],
+    ) -> Result<WasmWeCiphertext, JsError> {
+        let mut rng = rand::thread_rng();
+        on_curve!(&ck.commitment_key, |ck| {
+            let (domain_size, com) = decode_point::<E, _>(Kind::Commitment, com)?;
+            check_domain_size(ck.domain.size(), domain_size)?;
+            let ciphertext = kzg_we::encrypt(
+                &mut rng,
+                ck,
+                &com,
+                field_element(z)?,
+                field_element(y)?,
+                msg,
+            );
+            Ok(WasmWeCiphertext {
+                ciphertext: Curve(ciphertext),
+            })
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn decrypt(&self, opening: &WasmWeOpening) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.ciphertext, |ciphertext| {
+            let (_, opening) = same_curve!(&opening.opening);
+            Ok(kzg_we::decrypt(opening, ciphertext)?)
+        })
+    }
+
+    #[wasm_bindgen]
+    pub fn serialize(&self, ck: &WasmCommitmentKey) -> Result<Vec<u8>, JsError> {
+        on_curve!(&self.ciphertext, |ciphertext| Ok(
+            ciphertext.to_bytes(same_curve!(&ck.commitment_key))?
+        ))
+    }
+
+    #[wasm_bindgen]
+    pub fn deserialize(data: &[u8], ck: &WasmCommitmentKey) -> Result<WasmWeCiphertext, JsError> {
+        on_curve!(&ck.commitment_key, |ck| Ok(WasmWeCiphertext {
+            ciphertext: Curve(kzg_we::Ciphertext::from_bytes(data, ck)?),
+        }))
     }
 }
 
@@ -146,17 +948,212 @@ mod tests {
         let ck = WasmCommitmentKey::setup(32).expect("Failed to setup commitment key");
 
         // Serialize
-        let serialized = ck.serialize();
+        let serialized = ck.serialize().expect("Failed to serialize commitment key");
 
         // Deserialize
         let deserialized = WasmCommitmentKey::deserialize(&serialized)
             .expect("Failed to deserialize commitment key");
+        deserialized
+            .verify()
+            .expect("Deserialized commitment key should be valid");
 
         // Serialize again and compare bytes
-        let serialized_again = deserialized.serialize();
+        let serialized_again = deserialized
+            .serialize()
+            .expect("Failed to serialize commitment key");
         assert_eq!(
             serialized, serialized_again,
             "Serialized bytes should match"
         );
     }
+
+    #[test]
+    fn test_curves() {
+        for curve_id in [Bn254::CURVE_ID, Bls12_377::CURVE_ID] {
+            let ck = WasmCommitmentKey::setup_for_curve(curve_id, 8)
+                .expect("Failed to setup commitment key");
+            let bytes = ck.serialize().expect("Failed to serialize commitment key");
+            let ck = WasmCommitmentKey::deserialize(&bytes)
+                .expect("Failed to deserialize commitment key");
+            assert_eq!(ck.curve_id(), curve_id);
+
+            let receiver = WasmReceiver::new(&ck, vec![0, 1]).expect("Failed to commit");
+            let commitment = receiver
+                .commitment()
+                .expect("Failed to serialize commitment");
+            let sender = WasmSender::new(&ck, &commitment).expect("Failed to create sender");
+            let msg = sender
+                .send(1, &[0u8; 16], &[1u8; 16])
+                .expect("Failed to send");
+            let bytes = msg.serialize(&ck).expect("Failed to serialize message");
+            let msg = WasmMessage::deserialize(&bytes, &ck).expect("Failed to deserialize");
+            assert_eq!(
+                receiver.recv(1, &msg).expect("Failed to receive"),
+                vec![1u8; 16]
+            );
+        }
+
+        let ceremony =
+            WasmCeremony::new_for_curve(Bn254::CURVE_ID, 4).expect("Failed to start ceremony");
+        let bytes = ceremony.serialize().expect("Failed to serialize ceremony");
+        let mut ceremony = WasmCeremony::deserialize(&bytes).expect("Failed to deserialize");
+        ceremony.contribute();
+        let ck = ceremony
+            .commitment_key()
+            .expect("Failed to derive commitment key");
+        assert_eq!(ck.curve_id(), Bn254::CURVE_ID);
+    }
+
+    #[test]
+    fn test_sender_precomputation_transfer() {
+        let ck = WasmCommitmentKey::setup(8).expect("Failed to setup commitment key");
+        let receiver = WasmReceiver::new(&ck, vec![0, 1, 1, 0]).expect("Failed to commit");
+        let commitment = receiver
+            .commitment()
+            .expect("Failed to serialize commitment");
+
+        // e.g. computed in a worker
+        let mut sender = WasmSender::new(&ck, &commitment).expect("Failed to create sender");
+        let tables = sender
+            .export_precomputation()
+            .expect("Failed to export precomputation");
+
+        // e.g. loaded on the main thread
+        let mut sender = WasmSender::new(&ck, &commitment).expect("Failed to create sender");
+        sender
+            .import_precomputation(&tables)
+            .expect("Failed to import precomputation");
+
+        let msg = sender
+            .send(2, &[0u8; 16], &[1u8; 16])
+            .expect("Failed to send");
+        let res = receiver.recv(2, &msg).expect("Failed to receive");
+        assert_eq!(res, vec![1u8; 16]);
+    }
+
+    #[test]
+    fn test_message_transfer() {
+        let ck = WasmCommitmentKey::setup(8).expect("Failed to setup commitment key");
+        let mut receiver = WasmReceiver::new(&ck, vec![1, 0, 1]).expect("Failed to commit");
+        let commitment = receiver
+            .commitment()
+            .expect("Failed to serialize commitment");
+        let sender = WasmSender::new(&ck, &commitment).expect("Failed to create sender");
+
+        // the garbler checks that the evaluator committed to bits
+        let proof = receiver.prove_bits(&ck).expect("Failed to prove");
+        assert!(sender
+            .verify_bits(&ck, 3, &proof)
+            .expect("Failed to verify"));
+
+        // and that the first bit is set and the bits encode at least 5
+        let mut cs = WasmConstraintSystem::new();
+        cs.bits(0, 3);
+        cs.fixed(0, 1);
+        cs.at_least(0, 3, 5).expect("Failed to compare");
+        let proof = receiver
+            .prove_constraints(&ck, &cs)
+            .expect("Failed to prove");
+        assert!(sender
+            .verify_constraints(&ck, &cs, &proof)
+            .expect("Failed to verify"));
+
+        // the garbler sends all messages in one batch
+        let mut batch = WasmMessageBatch::new();
+        for i in 0..3 {
+            let msg = sender
+                .send(i, &[2 * i as u8; 16], &[2 * i as u8 + 1; 16])
+                .expect("Failed to send");
+            batch.push(&msg);
+        }
+        let bytes = batch.serialize(&ck).expect("Failed to serialize batch");
+
+        // the evaluator receives them on another machine
+        let batch = WasmMessageBatch::deserialize(&bytes, &ck).expect("Failed to deserialize");
+        assert_eq!(batch.len(), 3);
+        for (i, bit) in [1u8, 0, 1].into_iter().enumerate() {
+            let msg = batch.get(i).expect("Missing message");
+            let res = receiver.recv(i, &msg).expect("Failed to receive");
+            assert_eq!(res, vec![2 * i as u8 + bit; 16]);
+        }
+
+        // single messages
+        let msg = batch.get(0).expect("Missing message");
+        let bytes = msg.serialize(&ck).expect("Failed to serialize message");
+        let msg = WasmMessage::deserialize(&bytes, &ck).expect("Failed to deserialize");
+        assert_eq!(
+            receiver.recv(0, &msg).expect("Failed to receive"),
+            vec![1u8; 16]
+        );
+    }
+
+    #[test]
+    fn test_garbled_circuit() {
+        // the garbler holds a, the evaluator b and c, the output is (a & b) | !c
+        let circuit = WasmCircuit::parse(
+            "4 7\n3 1 1 1\n1 1\n\n2 1 0 1 3 AND\n1 1 2 4 INV\n2 1 3 4 5 OR\n2 1 5 5 6 AND\n",
+        )
+        .expect("Failed to parse");
+        assert_eq!(circuit.input_widths(), vec![1, 1, 1]);
+        assert_eq!(
+            circuit.evaluate(&[1, 1, 1]).expect("Failed to evaluate"),
+            vec![1]
+        );
+
+        let ck = WasmCommitmentKey::setup(4).expect("Failed to setup commitment key");
+        let receiver = WasmReceiver::new(&ck, vec![1, 1]).expect("Failed to commit");
+        let commitment = receiver
+            .commitment()
+            .expect("Failed to serialize commitment");
+        let sender = WasmSender::new(&ck, &commitment).expect("Failed to create sender");
+
+        // the evaluator gets its labels with laconic OT
+        let garbler = WasmGarbler::new(&circuit);
+        let mut labels = garbler.encode(0, vec![0]).expect("Failed to encode");
+        for i in 0..2 {
+            let m0 = garbler.label(i + 1, 0).expect("Missing label");
+            let m1 = garbler.label(i + 1, 1).expect("Missing label");
+            let msg = sender.send(i, &m0, &m1).expect("Failed to send");
+            labels.extend(receiver.recv(i, &msg).expect("Failed to receive"));
+        }
+
+        let bytes = garbler
+            .garbled_circuit()
+            .serialize()
+            .expect("Failed to serialize");
+        let garbled = WasmGarbledCircuit::deserialize(&bytes).expect("Failed to deserialize");
+        assert_eq!(
+            garbled
+                .evaluate(&circuit, &labels)
+                .expect("Failed to evaluate"),
+            vec![0]
+        );
+    }
+
+    #[test]
+    fn test_witness_encryption() {
+        let ck = WasmCommitmentKey::setup(4).expect("Failed to setup commitment key");
+
+        let mut evals = Vec::new();
+        for v in 1u8..=4 {
+            let mut x = [0u8; FIELD_SIZE];
+            x[0] = v;
+            evals.extend_from_slice(&x);
+        }
+        let poly = WasmWePolynomial::new(&evals).expect("Failed to parse evaluations");
+        let com = poly.commitment(&ck).expect("Failed to commit");
+
+        // a point outside of the domain
+        let z = [7u8; FIELD_SIZE];
+        let opening = poly.open(&ck, &z).expect("Failed to open");
+        let opening = opening.serialize(&ck).expect("Failed to serialize opening");
+        let opening = WasmWeOpening::deserialize(&opening, &ck).expect("Failed to deserialize");
+        let y = opening.value().expect("Failed to serialize value");
+
+        let ct =
+            WasmWeCiphertext::encrypt(&ck, &com, &z, &y, b"secret").expect("Failed to encrypt");
+        let ct = ct.serialize(&ck).expect("Failed to serialize ciphertext");
+        let ct = WasmWeCiphertext::deserialize(&ct, &ck).expect("Failed to deserialize");
+        assert_eq!(ct.decrypt(&opening).expect("Failed to decrypt"), b"secret");
+    }
 }
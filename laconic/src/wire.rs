@@ -0,0 +1,278 @@
+use ark_bls12_377::Bls12_377;
+use ark_bls12_381::Bls12_381;
+use ark_bn254::Bn254;
+use ark_ec::pairing::Pairing;
+use ark_ec::AffineRepr;
+use ark_serialize::{CanonicalDeserialize, CanonicalSerialize, Compress, Validate};
+
+use crate::error::LaconicError;
+
+// This module defines the binary format in which objects are
+// exchanged between the parties. Every object is prefixed by a header:
+//
+//   magic (4 bytes) | version (1) | kind (1) | curve id (1) | domain size (8, LE)
+//
+// followed by the compressed canonical serialization of the object.
+// Objects that do not depend on a curve or a commitment key, such as
+// garbled circuits, have curve id NO_CURVE and domain size 0.
+// Points are validated (on curve and in the prime order subgroup)
+// when decoding, and trailing bytes are rejected.
+
+/// identifies laconic OT objects
+pub const MAGIC: [u8; 4] = *b"LCOT";
+
+/// current version of the format. Version 2 added the hiding
+/// parts to openings, commitment keys and the receiver state
+pub const VERSION: u8 = 2;
+
+/// length of the header in bytes
+pub const HEADER_SIZE: usize = 15;
+
+/// the kind of object that follows the header
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+#[repr(u8)]
+pub enum Kind {
+    CommitmentKey = 1,
+    Commitment = 2,
+    Message = 3,
+    ReceiverState = 4,
+    MessageBatch = 5,
+    MessageN = 6,
+    WeCiphertext = 7,
+    Opening = 8,
+    MultiOpening = 9,
+    BitsProof = 10,
+    ConstraintProof = 11,
+    GarbledCircuit = 12,
+    GarbledInput = 13,
+    Ceremony = 14,
+}
+
+/// curve id of objects that do not depend on a curve
+pub const NO_CURVE: u8 = 0;
+
+/// Pairings that have an identifier in the wire format
+pub trait CurveId: Pairing {
+    const CURVE_ID: u8;
+}
+
+impl CurveId for Bls12_381 {
+    const CURVE_ID: u8 = 1;
+}
+
+impl CurveId for Bn254 {
+    const CURVE_ID: u8 = 2;
+}
+
+impl CurveId for Bls12_377 {
+    const CURVE_ID: u8 = 3;
+}
+
+/// Encode obj with a header for the given kind, curve and domain size
+pub fn encode<E: CurveId, T: CanonicalSerialize>(
+    kind: Kind,
+    domain_size: usize,
+    obj: &T,
+) -> Result<Vec<u8>, LaconicError> {
+    encode_with(kind, E::CURVE_ID, domain_size, obj)
+}
+
+/// Encode obj with a header for the given kind, without a curve
+pub fn encode_plain<T: CanonicalSerialize>(kind: Kind, obj: &T) -> Result<Vec<u8>, LaconicError> {
+    encode_with(kind, NO_CURVE, 0, obj)
+}
+
+fn encode_with<T: CanonicalSerialize>(
+    kind: Kind,
+    curve_id: u8,
+    domain_size: usize,
+    obj: &T,
+) -> Result<Vec<u8>, LaconicError> {
+    let mut bytes = Vec::with_capacity(HEADER_SIZE + obj.compressed_size());
+    bytes.extend_from_slice(&MAGIC);
+    bytes.push(VERSION);
+    bytes.push(kind as u8);
+    bytes.push(curve_id);
+    bytes.extend_from_slice(&(domain_size as u64).to_le_bytes());
+    obj.serialize_compressed(&mut bytes)?;
+    Ok(bytes)
+}
+
+/// Decode an object of the given kind and curve.
+/// Returns the domain size stored in the header together with the object
+pub fn decode<E: CurveId, T: CanonicalDeserialize>(
+    kind: Kind,
+    bytes: &[u8],
+) -> Result<(usize, T), LaconicError> {
+    decode_with(kind, E::CURVE_ID, bytes)
+}
+
+/// Decode an object of the given kind encoded with encode_plain
+pub fn decode_plain<T: CanonicalDeserialize>(kind: Kind, bytes: &[u8]) -> Result<T, LaconicError> {
+    let (_, obj) = decode_with(kind, NO_CURVE, bytes)?;
+    Ok(obj)
+}
+
+fn decode_with<T: CanonicalDeserialize>(
+    kind: Kind,
+    curve_id: u8,
+    bytes: &[u8],
+) -> Result<(usize, T), LaconicError> {
+    let (domain_size, mut payload) = read_header(kind, curve_id, bytes)?;
+    let obj = T::deserialize_with_mode(&mut payload, Compress::Yes, Validate::Yes)?;
+    if !payload.is_empty() {
+        return Err(LaconicError::Serialization(format!(
+            "{} trailing bytes",
+            payload.len()
+        )));
+    }
+    Ok((domain_size, obj))
+}
+
+/// Decode a single point of the given kind. Unlike decode,
+/// this tells apart malformed points from points outside the subgroup
+pub fn decode_point<E: CurveId, P: AffineRepr>(
+    kind: Kind,
+    bytes: &[u8],
+) -> Result<(usize, P), LaconicError> {
+    let (domain_size, payload) = read_header(kind, E::CURVE_ID, bytes)?;
+    if payload.len() != P::zero().compressed_size() {
+        return Err(LaconicError::MalformedPoint);
+    }
+    // decompression only succeeds for points on the curve
+    let p =
+        P::deserialize_compressed_unchecked(payload).map_err(|_| LaconicError::MalformedPoint)?;
+    p.check().map_err(|_| LaconicError::SubgroupCheckFailed)?;
+    Ok((domain_size, p))
+}
+
+/// Check that the domain size of a decoded object
+/// matches the domain size of the commitment key
+pub fn check_domain_size(expected: usize, found: usize) -> Result<(), LaconicError> {
+    if expected != found {
+        return Err(LaconicError::DomainMismatch { expected, found });
+    }
+    Ok(())
+}
+
+/// The curve id in the header of an encoded object,
+/// e.g. to select the curve before decoding it
+pub fn curve_of(bytes: &[u8]) -> Result<u8, LaconicError> {
+    let (header, _) = split_header(bytes)?;
+    Ok(header[6])
+}
+
+fn read_header(kind: Kind, curve_id: u8, bytes: &[u8]) -> Result<(usize, &[u8]), LaconicError> {
+    let (header, payload) = split_header(bytes)?;
+    if header[5] != kind as u8 {
+        return Err(LaconicError::Serialization(format!(
+            "expected object of kind {}, got {}",
+            kind as u8, header[5]
+        )));
+    }
+    if header[6] != curve_id {
+        return Err(LaconicError::CurveMismatch {
+            expected: curve_id,
+            found: header[6],
+        });
+    }
+    let domain_size = u64::from_le_bytes(header[7..15].try_into().unwrap());
+    let domain_size = usize::try_from(domain_size)
+        .map_err(|_| LaconicError::Serialization("domain size too large".into()))?;
+    Ok((domain_size, payload))
+}
+
+/// check the magic bytes and the version
+fn split_header(bytes: &[u8]) -> Result<(&[u8], &[u8]), LaconicError> {
+    if bytes.len() < HEADER_SIZE {
+        return Err(LaconicError::Serialization(format!(
+            "expected at least {} bytes, got {}",
+            HEADER_SIZE,
+            bytes.len()
+        )));
+    }
+    let (header, payload) = bytes.split_at(HEADER_SIZE);
+    if header[0..4] != MAGIC {
+        return Err(LaconicError::Serialization("invalid magic bytes".into()));
+    }
+    if header[4] != VERSION {
+        return Err(LaconicError::UnsupportedVersion { version: header[4] });
+    }
+    Ok((header, payload))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ark_bls12_381::G1Affine;
+    use ark_std::{test_rng, UniformRand};
+
+    #[test]
+    fn test_encode_decode() {
+        let rng = &mut test_rng();
+        let p = G1Affine::rand(rng);
+
+        let bytes = encode::<Bls12_381, _>(Kind::Commitment, 8, &p).unwrap();
+        assert_eq!(bytes.len(), HEADER_SIZE + p.compressed_size());
+
+        let (domain_size, q) = decode::<Bls12_381, G1Affine>(Kind::Commitment, &bytes).unwrap();
+        assert_eq!(domain_size, 8);
+        assert_eq!(p, q);
+        let (_, q) = decode_point::<Bls12_381, G1Affine>(Kind::Commitment, &bytes).unwrap();
+        assert_eq!(p, q);
+        assert_eq!(curve_of(&bytes).unwrap(), 1);
+
+        // wrong kind
+        let res = decode::<Bls12_381, G1Affine>(Kind::Message, &bytes);
+        assert!(matches!(res, Err(LaconicError::Serialization(_))));
+
+        // unknown version
+        let mut bad = bytes.clone();
+        bad[4] = VERSION + 1;
+        let res = decode::<Bls12_381, G1Affine>(Kind::Commitment, &bad);
+        assert_eq!(
+            res.err(),
+            Some(LaconicError::UnsupportedVersion {
+                version: VERSION + 1
+            })
+        );
+
+        // different curve
+        let mut bad = bytes.clone();
+        bad[6] = 0;
+        let res = decode::<Bls12_381, G1Affine>(Kind::Commitment, &bad);
+        assert_eq!(
+            res.err(),
+            Some(LaconicError::CurveMismatch {
+                expected: 1,
+                found: 0
+            })
+        );
+
+        // trailing bytes
+        let mut bad = bytes.clone();
+        bad.push(0);
+        let res = decode::<Bls12_381, G1Affine>(Kind::Commitment, &bad);
+        assert!(matches!(res, Err(LaconicError::Serialization(_))));
+
+        // truncated
+        let res = decode::<Bls12_381, G1Affine>(Kind::Commitment, &bytes[..HEADER_SIZE - 1]);
+        assert!(matches!(res, Err(LaconicError::Serialization(_))));
+
+        // objects without a curve
+        let bytes = encode_plain(Kind::GarbledCircuit, &vec![1u8, 2, 3]).unwrap();
+        assert_eq!(curve_of(&bytes).unwrap(), NO_CURVE);
+        assert_eq!(
+            decode_plain::<Vec<u8>>(Kind::GarbledCircuit, &bytes).unwrap(),
+            vec![1, 2, 3]
+        );
+        let res = decode::<Bls12_381, Vec<u8>>(Kind::GarbledCircuit, &bytes);
+        assert_eq!(
+            res.err(),
+            Some(LaconicError::CurveMismatch {
+                expected: 1,
+                found: NO_CURVE
+            })
+        );
+    }
+}
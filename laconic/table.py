@@ -6,10 +6,11 @@ from fractions import Fraction
 
 PATH = "target/criterion"
 SIZES = list(range(3, 10))
+CURVE = os.environ.get("CURVE", "bls12_381")
 SUB_DIRS = [
-    ("Hash (Time)", "laconic_ot_commit"),
-    ("Send (Time)", "laconic_ot_send"),
-    ("Recv (Time)", "laconic_ot_recv"),
+    ("Hash (Time)", f"laconic_ot_{CURVE}_commit"),
+    ("Send (Time)", f"laconic_ot_{CURVE}_send_all"),
+    ("Recv (Time)", f"laconic_ot_{CURVE}_recv_all"),
 ]
 
 def fmt_time(ns):